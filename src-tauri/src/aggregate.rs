@@ -0,0 +1,438 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::k8s::{cached_namespace_logs, K8sLog, LogFetchSettings, LogQueryError, LogQueryTiming, LogSourceError, NamespaceLogsQuery};
+use crate::log_parser::format_timestamp;
+use crate::search::{log_field, parse_duration_ms, parse_number};
+
+// Log aggregation
+//
+// Metrics are computed over the same filtered result k8s_get_namespace_logs
+// pages through, grouped by any fields and optionally by time bucket, so
+// histograms and top-N tables need no log lines in the webview. Fields are
+// named as in search queries (`level`, `pod`, `ns`, `requestId`, ...);
+// numeric fields accept durations (read as milliseconds) and sizes (bytes).
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Metric {
+    Count,
+    DistinctCount { field: String },
+    Min { field: String },
+    Max { field: String },
+    Avg { field: String },
+    Sum { field: String },
+    // `percentile` from 0 to 100, e.g. 95 for p95
+    Percentile { field: String, percentile: f64 },
+}
+
+impl Metric {
+    fn field(&self) -> Option<&str> {
+        match self {
+            Metric::Count => None,
+            Metric::DistinctCount { field }
+            | Metric::Min { field }
+            | Metric::Max { field }
+            | Metric::Avg { field }
+            | Metric::Sum { field }
+            | Metric::Percentile { field, .. } => Some(field),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AggregationRequest {
+    // Defaults to a single count
+    pub metrics: Vec<Metric>,
+    pub group_by: Vec<String>,
+    // Time bucket width such as "1m" or "15m"
+    pub bucket: Option<String>,
+    // Metric the groups are ranked by; defaults to the first
+    pub order_by: Option<usize>,
+    pub ascending: bool,
+    // Keep only the top groups, ranked over all buckets
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AggregationRow {
+    // Bucket start, when bucketing by time
+    pub bucket: Option<String>,
+    // One value per `group_by` field; null where the field is missing
+    pub group: Vec<Option<String>>,
+    // One value per metric; null when no line had a number for the field
+    pub values: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AggregationResponse {
+    pub metrics: Vec<Metric>,
+    pub group_by: Vec<String>,
+    pub bucket_ms: Option<u64>,
+    pub rows: Vec<AggregationRow>,
+    // Groups before `limit` was applied
+    pub groups_total: usize,
+    // Log events the metrics were computed over
+    pub total: usize,
+    pub errors: Vec<LogSourceError>,
+    pub sources_total: usize,
+    pub sources_failed: usize,
+    pub timing: LogQueryTiming,
+}
+
+// Running state for one row: a count plus, per metric, the values it needs
+struct Accumulator {
+    count: usize,
+    distinct: Vec<HashSet<String>>,
+    values: Vec<Vec<f64>>,
+}
+
+impl Accumulator {
+    fn new(metrics: usize) -> Self {
+        Accumulator {
+            count: 0,
+            distinct: vec![HashSet::new(); metrics],
+            values: vec![Vec::new(); metrics],
+        }
+    }
+
+    fn add(&mut self, log: &K8sLog, metrics: &[Metric]) {
+        self.count += 1;
+        for (i, metric) in metrics.iter().enumerate() {
+            let Some(value) = metric.field().and_then(|field| log_field(log, field)) else {
+                continue;
+            };
+            match metric {
+                Metric::Count => {}
+                Metric::DistinctCount { .. } => {
+                    self.distinct[i].insert(value.to_string());
+                }
+                _ => {
+                    if let Some(number) = parse_number(value).filter(|n| n.is_finite()) {
+                        self.values[i].push(number);
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish(mut self, metrics: &[Metric]) -> Vec<Option<f64>> {
+        metrics.iter().enumerate()
+            .map(|(i, metric)| {
+                let values = &mut self.values[i];
+                match metric {
+                    Metric::Count => Some(self.count as f64),
+                    Metric::DistinctCount { .. } => Some(self.distinct[i].len() as f64),
+                    Metric::Min { .. } => values.iter().copied().reduce(f64::min),
+                    Metric::Max { .. } => values.iter().copied().reduce(f64::max),
+                    Metric::Sum { .. } => (!values.is_empty()).then(|| values.iter().sum()),
+                    Metric::Avg { .. } => (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64),
+                    Metric::Percentile { percentile, .. } => percentile_of(values, *percentile),
+                }
+            })
+            .collect()
+    }
+}
+
+// Linear interpolation between the closest ranks
+fn percentile_of(values: &mut [f64], percentile: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let rank = percentile / 100.0 * (values.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    Some(values[lower] + (values[upper] - values[lower]) * (rank - lower as f64))
+}
+
+fn bucket_start(timestamp: &str, bucket_ms: i64) -> Option<String> {
+    let millis = DateTime::parse_from_rfc3339(timestamp).ok()?.timestamp_millis();
+    let start = millis.div_euclid(bucket_ms) * bucket_ms;
+    DateTime::<Utc>::from_timestamp_millis(start).map(|time| format_timestamp(&time))
+}
+
+fn validate(request: &AggregationRequest) -> Result<(), String> {
+    for metric in &request.metrics {
+        if let Metric::Percentile { percentile, .. } = metric {
+            if !(0.0..=100.0).contains(percentile) {
+                return Err(format!("Percentile must be between 0 and 100, got {}", percentile));
+            }
+        }
+        if metric.field().is_some_and(|field| field.trim().is_empty()) {
+            return Err("Metric field must not be empty".to_string());
+        }
+    }
+    if request.order_by.is_some_and(|index| index >= request.metrics.len().max(1)) {
+        return Err("order_by must name one of the metrics".to_string());
+    }
+    Ok(())
+}
+
+// Compute the rows for a set of logs; exposed separately from the command so
+// the result does not depend on where the logs came from
+pub fn aggregate_logs(logs: &[K8sLog], request: &AggregationRequest) -> Result<(Vec<AggregationRow>, usize, Option<u64>), String> {
+    validate(request)?;
+    let metrics = if request.metrics.is_empty() { vec![Metric::Count] } else { request.metrics.clone() };
+    let bucket_ms = match request.bucket.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+        Some(text) => {
+            let ms = parse_duration_ms(text)
+                .filter(|ms| *ms >= 1.0)
+                .ok_or_else(|| format!("Invalid bucket '{}': expected a duration like 1m or 15m", text))?;
+            Some(ms as i64)
+        }
+        None => None,
+    };
+
+    // Rows by (bucket, group), and the same metrics per group for ranking
+    let mut rows: BTreeMap<(Option<String>, Vec<Option<String>>), Accumulator> = BTreeMap::new();
+    let mut groups: HashMap<Vec<Option<String>>, Accumulator> = HashMap::new();
+    for log in logs {
+        let group: Vec<Option<String>> = request.group_by.iter()
+            .map(|field| log_field(log, field).map(str::to_string))
+            .collect();
+        let bucket = match bucket_ms {
+            Some(ms) => match bucket_start(&log.timestamp, ms) {
+                Some(start) => Some(start),
+                None => continue,
+            },
+            None => None,
+        };
+
+        if bucket.is_some() {
+            groups.entry(group.clone())
+                .or_insert_with(|| Accumulator::new(metrics.len()))
+                .add(log, &metrics);
+        }
+        rows.entry((bucket, group))
+            .or_insert_with(|| Accumulator::new(metrics.len()))
+            .add(log, &metrics);
+    }
+
+    let order_by = request.order_by.unwrap_or(0);
+    let rank = |a: &Option<f64>, b: &Option<f64>| {
+        // Groups without a value sort last either way
+        let ordering = match (a, b) {
+            (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if request.ascending { ordering } else { ordering.reverse() }
+    };
+
+    let mut rows: Vec<AggregationRow> = rows.into_iter()
+        .map(|((bucket, group), accumulator)| AggregationRow {
+            bucket,
+            group,
+            values: accumulator.finish(&metrics),
+        })
+        .collect();
+
+    let groups_total;
+    if bucket_ms.is_some() {
+        let mut ranked: Vec<(Vec<Option<String>>, Option<f64>)> = groups.into_iter()
+            .map(|(group, accumulator)| {
+                let value = accumulator.finish(&metrics)[order_by];
+                (group, value)
+            })
+            .collect();
+        groups_total = ranked.len();
+        ranked.sort_by(|a, b| rank(&a.1, &b.1).then_with(|| a.0.cmp(&b.0)));
+        if let Some(limit) = request.limit {
+            let kept: HashSet<Vec<Option<String>>> = ranked.into_iter().take(limit).map(|(group, _)| group).collect();
+            rows.retain(|row| kept.contains(&row.group));
+        }
+        // Already in bucket order from the map; rank groups within each bucket
+        rows.sort_by(|a, b| a.bucket.cmp(&b.bucket).then_with(|| rank(&a.values[order_by], &b.values[order_by])));
+    } else {
+        groups_total = rows.len();
+        rows.sort_by(|a, b| rank(&a.values[order_by], &b.values[order_by]).then_with(|| a.group.cmp(&b.group)));
+        if let Some(limit) = request.limit {
+            rows.truncate(limit);
+        }
+    }
+
+    Ok((rows, groups_total, bucket_ms.map(|ms| ms as u64)))
+}
+
+// Aggregate the filtered result of a namespace log query. Shares the result
+// cache with k8s_get_namespace_logs, so aggregating the query being viewed
+// does not fetch again.
+#[tauri::command]
+pub async fn k8s_aggregate_logs(
+    query: NamespaceLogsQuery,
+    aggregation: AggregationRequest,
+    refresh: Option<bool>,
+    concurrency: Option<usize>,
+    pod_timeout_secs: Option<u64>
+) -> Result<AggregationResponse, LogQueryError> {
+    validate(&aggregation)?;
+    let settings = LogFetchSettings::new(concurrency, pod_timeout_secs);
+    let (_, result) = cached_namespace_logs(&query, &settings, refresh.unwrap_or(false)).await?;
+    let (rows, groups_total, bucket_ms) = aggregate_logs(&result.logs, &aggregation)?;
+
+    Ok(AggregationResponse {
+        metrics: if aggregation.metrics.is_empty() { vec![Metric::Count] } else { aggregation.metrics },
+        group_by: aggregation.group_by,
+        bucket_ms,
+        rows,
+        groups_total,
+        total: result.logs.len(),
+        errors: result.errors.clone(),
+        sources_total: result.sources_total,
+        sources_failed: result.errors.len(),
+        timing: result.timing.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(timestamp: &str, pod: &str, latency: Option<&str>) -> K8sLog {
+        K8sLog {
+            timestamp: timestamp.to_string(),
+            level: "INFO".to_string(),
+            message: String::new(),
+            namespace: "default".to_string(),
+            pod: pod.to_string(),
+            container: "app".to_string(),
+            fields: latency.into_iter().map(|value| ("latency".to_string(), value.to_string())).collect(),
+            previous: false,
+            matched_fields: Vec::new(),
+        }
+    }
+
+    fn latency(op: fn(String) -> Metric) -> Metric {
+        op("latency".to_string())
+    }
+
+    fn percentile(percentile: f64) -> Metric {
+        Metric::Percentile { field: "latency".to_string(), percentile }
+    }
+
+    fn single_row(logs: &[K8sLog], metrics: Vec<Metric>) -> Vec<Option<f64>> {
+        let request = AggregationRequest { metrics, ..AggregationRequest::default() };
+        let (rows, _, _) = aggregate_logs(logs, &request).unwrap();
+        assert_eq!(rows.len(), 1);
+        rows[0].values.clone()
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_ranks() {
+        let mut values = vec![40.0, 10.0, 30.0, 20.0];
+        assert_eq!(percentile_of(&mut values, 50.0), Some(25.0));
+        assert_eq!(percentile_of(&mut values, 0.0), Some(10.0));
+        assert_eq!(percentile_of(&mut values, 100.0), Some(40.0));
+        // p99 of four values sits 97% of the way from the third to the fourth
+        assert!((percentile_of(&mut values, 99.0).unwrap() - 39.7).abs() < 1e-9);
+        assert_eq!(percentile_of(&mut [7.0], 99.0), Some(7.0));
+        assert_eq!(percentile_of(&mut [], 50.0), None);
+    }
+
+    #[test]
+    fn missing_and_non_numeric_fields_are_skipped() {
+        let logs = [
+            log("2024-01-01T10:00:00Z", "api-0", Some("100ms")),
+            log("2024-01-01T10:00:01Z", "api-0", Some("0.3s")),
+            log("2024-01-01T10:00:02Z", "api-0", Some("slow")),
+            log("2024-01-01T10:00:03Z", "api-0", None),
+        ];
+        let values = single_row(&logs, vec![
+            Metric::Count,
+            latency(|field| Metric::Avg { field }),
+            latency(|field| Metric::Min { field }),
+            latency(|field| Metric::Max { field }),
+            percentile(50.0),
+        ]);
+        assert_eq!(values, [Some(4.0), Some(200.0), Some(100.0), Some(300.0), Some(200.0)]);
+
+        let none = single_row(&logs[2..], vec![latency(|field| Metric::Sum { field }), percentile(99.0)]);
+        assert_eq!(none, [None, None]);
+    }
+
+    #[test]
+    fn buckets_align_to_their_width() {
+        let logs = [
+            log("2024-01-01T10:04:59.999Z", "api-0", None),
+            log("2024-01-01T10:05:00Z", "api-0", None),
+            log("2024-01-01T10:09:30+02:00", "api-0", None),
+        ];
+        let request = AggregationRequest { bucket: Some("5m".to_string()), ..AggregationRequest::default() };
+        let (rows, _, bucket_ms) = aggregate_logs(&logs, &request).unwrap();
+        assert_eq!(bucket_ms, Some(300_000));
+        let buckets: Vec<(&str, Option<f64>)> = rows.iter()
+            .map(|row| (row.bucket.as_deref().unwrap(), row.values[0]))
+            .collect();
+        assert_eq!(buckets, [
+            ("2024-01-01T08:05:00.000000000Z", Some(1.0)),
+            ("2024-01-01T10:00:00.000000000Z", Some(1.0)),
+            ("2024-01-01T10:05:00.000000000Z", Some(1.0)),
+        ]);
+
+        let bad = AggregationRequest { bucket: Some("5".to_string()), ..AggregationRequest::default() };
+        assert!(aggregate_logs(&logs, &bad).is_err());
+    }
+
+    #[test]
+    fn limit_ranks_groups_over_all_buckets() {
+        // api-0 is busiest overall, api-2 in the second minute alone
+        let logs = [
+            log("2024-01-01T10:00:00Z", "api-0", Some("5")),
+            log("2024-01-01T10:00:01Z", "api-0", Some("5")),
+            log("2024-01-01T10:00:02Z", "api-0", Some("5")),
+            log("2024-01-01T10:00:03Z", "api-1", Some("50")),
+            log("2024-01-01T10:01:00Z", "api-1", Some("50")),
+            log("2024-01-01T10:01:01Z", "api-2", Some("500")),
+            log("2024-01-01T10:01:02Z", "api-2", Some("500")),
+        ];
+        let request = AggregationRequest {
+            metrics: vec![Metric::Count, latency(|field| Metric::Max { field })],
+            group_by: vec!["pod".to_string()],
+            bucket: Some("1m".to_string()),
+            limit: Some(2),
+            ..AggregationRequest::default()
+        };
+        let rows = |request: &AggregationRequest| -> (Vec<(String, String)>, usize) {
+            let (rows, groups_total, _) = aggregate_logs(&logs, request).unwrap();
+            let rows = rows.into_iter()
+                .map(|row| (row.bucket.unwrap()[14..16].to_string(), row.group[0].clone().unwrap()))
+                .collect();
+            (rows, groups_total)
+        };
+
+        // By count api-0 (3) is first; api-1 and api-2 tie at 2 and the group name breaks
+        // the tie, so api-2 is dropped although it leads the second minute
+        let (by_count, groups_total) = rows(&request);
+        assert_eq!(groups_total, 3);
+        assert_eq!(by_count, [
+            ("00".to_string(), "api-0".to_string()),
+            ("00".to_string(), "api-1".to_string()),
+            ("01".to_string(), "api-1".to_string()),
+        ]);
+
+        // By max latency, ascending: api-0 then api-1; rows ranked within each bucket
+        let by_latency = AggregationRequest { order_by: Some(1), ascending: true, ..request.clone() };
+        assert_eq!(rows(&by_latency).0, [
+            ("00".to_string(), "api-0".to_string()),
+            ("00".to_string(), "api-1".to_string()),
+            ("01".to_string(), "api-1".to_string()),
+        ]);
+
+        // Descending by max latency keeps api-2 and api-1
+        let by_latency = AggregationRequest { order_by: Some(1), ..request.clone() };
+        assert_eq!(rows(&by_latency).0, [
+            ("00".to_string(), "api-1".to_string()),
+            ("01".to_string(), "api-2".to_string()),
+            ("01".to_string(), "api-1".to_string()),
+        ]);
+
+        let out_of_range = AggregationRequest { order_by: Some(2), ..request };
+        assert!(aggregate_logs(&logs, &out_of_range).is_err());
+    }
+}
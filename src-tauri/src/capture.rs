@@ -0,0 +1,485 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use futures::{AsyncBufReadExt, StreamExt};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{ListParams, LogParams};
+use kube::{Api, ResourceExt};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::task::AbortHandle;
+
+use crate::k8s::{describe_kube_error, get_k8s_client, K8sLog, WorkloadMembership};
+use crate::log_parser::{format_timestamp, parse_log_line};
+use crate::log_store::log_store;
+use crate::multiline::MultilineAssembler;
+
+// Background log capture
+//
+// A capture pins a namespace plus deployments and/or a label selector. Its
+// supervisor re-lists the matching pods every few seconds and keeps one
+// stream per running container, writing lines into the local log store.
+// Streams that drop are reopened from the last stored timestamp, streams that
+// fail are retried with a growing delay, and pods created by a rollout are
+// picked up on the next resync.
+
+// How often a capture re-lists its pods
+const CAPTURE_RESYNC_INTERVAL: Duration = Duration::from_secs(5);
+// Longest a received line waits before it is written to the store
+const CAPTURE_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+// Lines buffered per stream before an early write
+const CAPTURE_BATCH_LINES: usize = 500;
+// Longest wait before a failed stream is opened again; the wait doubles per failure
+const CAPTURE_RETRY_MAX: Duration = Duration::from_secs(300);
+// How long a source that stopped matching is still listed as Gone
+const CAPTURE_GONE_RETENTION: Duration = Duration::from_secs(600);
+
+// What to capture. At least one of `deployments` and `label_selector` is required.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CaptureSpec {
+    pub namespace: String,
+    pub deployments: Option<Vec<String>>,
+    // Kubernetes label selector, e.g. "app=api,tier!=canary"
+    pub label_selector: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum CaptureSourceState {
+    Connecting,
+    Streaming,
+    // The stream closed; it is reopened on the next resync if the container still runs
+    Disconnected,
+    Failed,
+    // The pod or container no longer matches, e.g. replaced by a rollout
+    Gone,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureSourceHealth {
+    pub pod: String,
+    pub container: String,
+    pub state: CaptureSourceState,
+    pub lines: u64,
+    pub last_timestamp: Option<String>,
+    pub last_error: Option<String>,
+    pub reconnects: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureInfo {
+    pub id: String,
+    pub spec: CaptureSpec,
+    pub started_at: String,
+    pub last_sync: Option<String>,
+    // Set when the last pod listing failed; sources keep their previous state
+    pub sync_error: Option<String>,
+    pub sources: Vec<CaptureSourceHealth>,
+}
+
+type SourceKey = (String, String);
+
+// Consecutive failures of a source and when it may be tried again
+struct SourceRetry {
+    failures: u32,
+    next_attempt: Instant,
+}
+
+#[derive(Default)]
+struct CaptureStatus {
+    last_sync: Option<DateTime<Utc>>,
+    sync_error: Option<String>,
+    sources: BTreeMap<SourceKey, CaptureSourceHealth>,
+    // When each Gone source stopped matching; it is dropped after CAPTURE_GONE_RETENTION
+    gone_at: HashMap<SourceKey, Instant>,
+    retries: HashMap<SourceKey, SourceRetry>,
+}
+
+fn lock_status(status: &Mutex<CaptureStatus>) -> Result<MutexGuard<'_, CaptureStatus>, String> {
+    status.lock().map_err(|_| "Capture status lock poisoned".to_string())
+}
+
+fn lock_streams(streams: &Mutex<HashMap<SourceKey, AbortHandle>>) -> Result<MutexGuard<'_, HashMap<SourceKey, AbortHandle>>, String> {
+    streams.lock().map_err(|_| "Capture streams lock poisoned".to_string())
+}
+
+// Wait before retrying a source that failed `failures` times in a row
+fn retry_delay(failures: u32) -> Duration {
+    CAPTURE_RESYNC_INTERVAL.saturating_mul(1 << failures.min(6)).min(CAPTURE_RETRY_MAX)
+}
+
+struct Capture {
+    spec: CaptureSpec,
+    started_at: DateTime<Utc>,
+    status: Arc<Mutex<CaptureStatus>>,
+    supervisor: AbortHandle,
+    streams: Arc<Mutex<HashMap<SourceKey, AbortHandle>>>,
+}
+
+impl Capture {
+    fn info(&self, id: &str) -> Result<CaptureInfo, String> {
+        let status = lock_status(&self.status)?;
+        Ok(CaptureInfo {
+            id: id.to_string(),
+            spec: self.spec.clone(),
+            started_at: format_timestamp(&self.started_at),
+            last_sync: status.last_sync.as_ref().map(format_timestamp),
+            sync_error: status.sync_error.clone(),
+            sources: status.sources.values().cloned().collect(),
+        })
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        self.supervisor.abort();
+        for (_, stream) in lock_streams(&self.streams)?.drain() {
+            stream.abort();
+        }
+        Ok(())
+    }
+}
+
+static CAPTURES: OnceLock<Mutex<HashMap<String, Capture>>> = OnceLock::new();
+static NEXT_CAPTURE_ID: AtomicU64 = AtomicU64::new(1);
+
+fn captures() -> &'static Mutex<HashMap<String, Capture>> {
+    CAPTURES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Containers with a live process to stream; finished init containers are skipped
+fn running_containers(pod: &Pod) -> Vec<String> {
+    let Some(ref status) = pod.status else {
+        return Vec::new();
+    };
+
+    status.container_statuses.iter()
+        .chain(status.init_container_statuses.iter())
+        .chain(status.ephemeral_container_statuses.iter())
+        .flatten()
+        .filter(|cs| cs.state.as_ref().is_some_and(|s| s.running.is_some()))
+        .map(|cs| cs.name.clone())
+        .collect()
+}
+
+async fn matching_pods(api: &Api<Pod>, spec: &CaptureSpec) -> Result<Vec<Pod>, String> {
+    let mut lp = ListParams::default();
+    if let Some(selector) = spec.label_selector.as_deref().filter(|s| !s.trim().is_empty()) {
+        lp = lp.labels(selector);
+    }
+    let pods = api.list(&lp).await
+        .map_err(|e| format!("Failed to list pods in namespace {}: {}", spec.namespace, describe_kube_error(e)))?;
+
+    let membership = match spec.deployments {
+        Some(ref names) if !names.is_empty() => Some(WorkloadMembership::resolve(&spec.namespace, names).await?),
+        _ => None,
+    };
+
+    Ok(pods.into_iter()
+        .filter(|pod| membership.as_ref().is_none_or(|m| m.contains(pod)))
+        .collect())
+}
+
+// Write a batch to the store off the async runtime
+async fn persist(app: &AppHandle, batch: Vec<K8sLog>) -> Result<(), String> {
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || {
+        let store = log_store(&app)?;
+        let mut store = store.lock().map_err(|_| "Log store lock poisoned".to_string())?;
+        store.append(&batch).map(|_| ())
+    })
+    .await
+    .map_err(|e| format!("Log store write failed: {}", e))?
+}
+
+fn update_source(status: &Mutex<CaptureStatus>, key: &SourceKey, update: impl FnOnce(&mut CaptureSourceHealth)) -> Result<(), String> {
+    if let Some(health) = lock_status(status)?.sources.get_mut(key) {
+        update(health);
+    }
+    Ok(())
+}
+
+// Stream one container into the store until the connection closes. `after`
+// is the timestamp of the last stored line; the API only resumes at whole
+// seconds, so lines up to it are dropped rather than stored twice.
+async fn capture_container(
+    app: AppHandle,
+    api: Api<Pod>,
+    namespace: String,
+    key: SourceKey,
+    since: DateTime<Utc>,
+    after: Option<String>,
+    status: Arc<Mutex<CaptureStatus>>,
+) -> Result<(), String> {
+    let (pod, container) = key.clone();
+    let lp = LogParams {
+        container: Some(container.clone()),
+        follow: true,
+        timestamps: true,
+        since_time: Some(since),
+        ..LogParams::default()
+    };
+
+    let result = async {
+        let stream = api.log_stream(&pod, &lp).await
+            .map_err(|e| format!("Failed to stream logs for pod {} container {}: {}", pod, container, describe_kube_error(e)))?;
+        update_source(&status, &key, |health| health.state = CaptureSourceState::Streaming)?;
+
+        let mut lines = stream.lines();
+        let mut assembler = MultilineAssembler::new();
+        let mut batch: Vec<K8sLog> = Vec::new();
+        // An event carries its first line's timestamp, so resuming goes by the
+        // last raw line: of the open event, and of the events in `batch`
+        let mut open_through: Option<String> = None;
+        let mut batch_through: Option<String> = None;
+        let mut last_flush = Instant::now();
+        loop {
+            // Wake up now and then so a quiet stream still gets flushed
+            let closed = match tokio::time::timeout(CAPTURE_FLUSH_INTERVAL, lines.next()).await {
+                Ok(Some(line)) => {
+                    let line = line.map_err(|e| format!("Log stream for pod {} container {} failed: {}", pod, container, e))?;
+                    if !line.trim().is_empty() {
+                        let log = parse_log_line(&line, &namespace, &pod, &container);
+                        if after.as_ref().is_none_or(|after| &log.timestamp > after) {
+                            let timestamp = log.timestamp.clone();
+                            if let Some(event) = assembler.push(log) {
+                                batch.push(event);
+                                batch_through = open_through.take();
+                            }
+                            open_through = Some(timestamp);
+                        }
+                    }
+                    false
+                }
+                Ok(None) => {
+                    batch.extend(assembler.finish());
+                    batch_through = open_through.take().or(batch_through);
+                    true
+                }
+                // A stack trace arrives in one burst, so a quiet stream means the open event is complete
+                Err(_) => {
+                    batch.extend(assembler.finish());
+                    batch_through = open_through.take().or(batch_through);
+                    false
+                }
+            };
+
+            let due = closed || batch.len() >= CAPTURE_BATCH_LINES || last_flush.elapsed() >= CAPTURE_FLUSH_INTERVAL;
+            if due && !batch.is_empty() {
+                let written = batch.len() as u64;
+                let last_timestamp = batch_through.clone();
+                persist(&app, std::mem::take(&mut batch)).await?;
+                update_source(&status, &key, |health| {
+                    health.lines += written;
+                    health.last_timestamp = last_timestamp;
+                })?;
+            }
+            if due {
+                last_flush = Instant::now();
+            }
+            if closed {
+                return Ok::<(), String>(());
+            }
+        }
+    }.await;
+
+    update_source(&status, &key, |health| match result {
+        Ok(()) => health.state = CaptureSourceState::Disconnected,
+        Err(error) => {
+            health.state = CaptureSourceState::Failed;
+            health.last_error = Some(error);
+        }
+    })
+}
+
+// Keep the capture's streams in line with the pods that currently match.
+// Only returns when the capture's state can no longer be read.
+async fn supervise_capture(
+    app: AppHandle,
+    spec: CaptureSpec,
+    started_at: DateTime<Utc>,
+    status: Arc<Mutex<CaptureStatus>>,
+    streams: Arc<Mutex<HashMap<SourceKey, AbortHandle>>>,
+) -> Result<(), String> {
+    loop {
+        let synced = async {
+            let client = get_k8s_client().map_err(|e| e.to_string())?;
+            let api: Api<Pod> = Api::namespaced(client, &spec.namespace);
+            let pods = matching_pods(&api, &spec).await?;
+            Ok::<_, String>((api, pods))
+        }.await;
+
+        match synced {
+            Ok((api, pods)) => {
+                let live: HashSet<SourceKey> = pods.iter()
+                    .flat_map(|pod| {
+                        let name = pod.name_any();
+                        running_containers(pod).into_iter().map(move |container| (name.clone(), container))
+                    })
+                    .collect();
+
+                let mut status_guard = lock_status(&status)?;
+                let mut streams_guard = lock_streams(&streams)?;
+                let status_guard = &mut *status_guard;
+                let now = Instant::now();
+                status_guard.last_sync = Some(Utc::now());
+                status_guard.sync_error = None;
+
+                for key in &live {
+                    status_guard.gone_at.remove(key);
+                    let health = status_guard.sources.entry(key.clone()).or_insert_with(|| CaptureSourceHealth {
+                        pod: key.0.clone(),
+                        container: key.1.clone(),
+                        state: CaptureSourceState::Connecting,
+                        lines: 0,
+                        last_timestamp: None,
+                        last_error: None,
+                        reconnects: 0,
+                    });
+
+                    let needs_stream = match health.state {
+                        CaptureSourceState::Connecting => !streams_guard.contains_key(key),
+                        CaptureSourceState::Streaming => {
+                            status_guard.retries.remove(key);
+                            false
+                        }
+                        // Back off a source that keeps failing, e.g. on a missing permission
+                        CaptureSourceState::Failed => {
+                            let retry = status_guard.retries.entry(key.clone())
+                                .or_insert(SourceRetry { failures: 0, next_attempt: now });
+                            let due = now >= retry.next_attempt;
+                            if due {
+                                retry.failures += 1;
+                                retry.next_attempt = now + retry_delay(retry.failures);
+                                health.reconnects += 1;
+                            }
+                            due
+                        }
+                        CaptureSourceState::Disconnected | CaptureSourceState::Gone => {
+                            health.reconnects += 1;
+                            true
+                        }
+                    };
+                    if !needs_stream {
+                        continue;
+                    }
+
+                    // Resume where the store left off, or from when the capture started
+                    let since = health.last_timestamp.as_deref()
+                        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                        .map(|t| t.with_timezone(&Utc))
+                        .unwrap_or(started_at);
+                    health.state = CaptureSourceState::Connecting;
+                    let task = tokio::spawn(capture_container(
+                        app.clone(),
+                        api.clone(),
+                        spec.namespace.clone(),
+                        key.clone(),
+                        since,
+                        health.last_timestamp.clone(),
+                        status.clone(),
+                    ));
+                    if let Some(old) = streams_guard.insert(key.clone(), task.abort_handle()) {
+                        old.abort();
+                    }
+                }
+
+                for (key, health) in status_guard.sources.iter_mut() {
+                    if !live.contains(key) && health.state != CaptureSourceState::Gone {
+                        if let Some(stream) = streams_guard.remove(key) {
+                            stream.abort();
+                        }
+                        health.state = CaptureSourceState::Gone;
+                        status_guard.gone_at.insert(key.clone(), now);
+                        status_guard.retries.remove(key);
+                    }
+                }
+
+                // Rollouts replace pods for as long as the capture runs
+                let expired: Vec<SourceKey> = status_guard.gone_at.iter()
+                    .filter(|(_, gone)| now.duration_since(**gone) >= CAPTURE_GONE_RETENTION)
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                for key in expired {
+                    status_guard.gone_at.remove(&key);
+                    status_guard.sources.remove(&key);
+                }
+            }
+            Err(error) => {
+                let mut status_guard = lock_status(&status)?;
+                status_guard.last_sync = Some(Utc::now());
+                status_guard.sync_error = Some(error);
+            }
+        }
+
+        tokio::time::sleep(CAPTURE_RESYNC_INTERVAL).await;
+    }
+}
+
+// Start capturing a workload's logs into the local store in the background
+#[tauri::command]
+pub async fn k8s_start_capture(app: AppHandle, spec: CaptureSpec) -> Result<CaptureInfo, String> {
+    if spec.namespace.trim().is_empty() {
+        return Err("Namespace is required".to_string());
+    }
+    let has_deployments = spec.deployments.as_ref().is_some_and(|d| !d.is_empty());
+    let has_selector = spec.label_selector.as_ref().is_some_and(|s| !s.trim().is_empty());
+    if !has_deployments && !has_selector {
+        return Err("A capture needs deployments or a label selector".to_string());
+    }
+
+    // Fail early on a bad selector, missing permissions or an unreachable cluster
+    let client = get_k8s_client().map_err(|e| e.to_string())?;
+    let api: Api<Pod> = Api::namespaced(client, &spec.namespace);
+    matching_pods(&api, &spec).await?;
+    log_store(&app)?;
+
+    let id = format!("capture-{}", NEXT_CAPTURE_ID.fetch_add(1, Ordering::Relaxed));
+    let started_at = Utc::now();
+    let status = Arc::new(Mutex::new(CaptureStatus::default()));
+    let streams = Arc::new(Mutex::new(HashMap::new()));
+    let supervisor = tokio::spawn(supervise_capture(
+        app,
+        spec.clone(),
+        started_at,
+        status.clone(),
+        streams.clone(),
+    ));
+
+    let capture = Capture {
+        spec,
+        started_at,
+        status,
+        supervisor: supervisor.abort_handle(),
+        streams,
+    };
+    let info = capture.info(&id)?;
+    captures().lock()
+        .map_err(|_| "Captures lock poisoned".to_string())?
+        .insert(id, capture);
+
+    Ok(info)
+}
+
+// Stop a capture; what it already stored stays in the log store
+#[tauri::command]
+pub async fn k8s_stop_capture(capture_id: String) -> Result<(), String> {
+    let capture = captures().lock()
+        .map_err(|_| "Captures lock poisoned".to_string())?
+        .remove(&capture_id)
+        .ok_or_else(|| format!("Unknown capture {}", capture_id))?;
+    capture.stop()
+}
+
+// Running captures with the health of every source they have seen
+#[tauri::command]
+pub async fn k8s_list_captures() -> Result<Vec<CaptureInfo>, String> {
+    let captures = captures().lock()
+        .map_err(|_| "Captures lock poisoned".to_string())?;
+    let mut infos: Vec<CaptureInfo> = captures.iter()
+        .map(|(id, capture)| capture.info(id))
+        .collect::<Result<_, _>>()?;
+    infos.sort_by(|a, b| a.started_at.cmp(&b.started_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(infos)
+}
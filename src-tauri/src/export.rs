@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use futures::StreamExt;
+use tokio::sync::mpsc;
+
+use crate::k8s::{namespace_log_sources, K8sLog, LogFetchSettings, LogQueryError, LogSourceError, NamespaceLogsQuery};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    // One K8sLog JSON object per line
+    Ndjson,
+    Csv,
+    // The message of each line as the container wrote it
+    Text,
+    // `timestamp level [namespace/pod/container] message`
+    Log,
+}
+
+const CSV_HEADER: &str = "timestamp,level,namespace,pod,container,previous,message,fields";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportSummary {
+    pub path: String,
+    pub format: ExportFormat,
+    pub gzip: bool,
+    pub lines: usize,
+    pub bytes: u64,
+    // Sources that could not be read, so the export is known to be partial
+    pub errors: Vec<LogSourceError>,
+}
+
+// Quote a CSV value when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_log<W: Write>(out: &mut W, format: ExportFormat, log: &K8sLog) -> std::io::Result<()> {
+    match format {
+        ExportFormat::Ndjson => {
+            serde_json::to_writer(&mut *out, log)?;
+            out.write_all(b"\n")
+        }
+        ExportFormat::Csv => {
+            let fields = if log.fields.is_empty() {
+                String::new()
+            } else {
+                serde_json::to_string(&log.fields).unwrap_or_default()
+            };
+            let row = [
+                csv_field(&log.timestamp),
+                csv_field(&log.level),
+                csv_field(&log.namespace),
+                csv_field(&log.pod),
+                csv_field(&log.container),
+                log.previous.to_string(),
+                csv_field(&log.message),
+                csv_field(&fields),
+            ];
+            writeln!(out, "{}", row.join(","))
+        }
+        ExportFormat::Text => writeln!(out, "{}", log.message),
+        ExportFormat::Log => {
+            writeln!(out, "{} {} [{}/{}/{}] {}", log.timestamp, log.level, log.namespace, log.pod, log.container, log.message)
+        }
+    }
+}
+
+// Batches of logs handed from the fetch to the file writer; small so a slow
+// disk holds back the fetch instead of buffering the namespace in memory
+const EXPORT_QUEUE_BATCHES: usize = 4;
+
+// Write each batch from `batches` through `out` as it arrives
+fn write_batches<W: Write>(out: &mut W, format: ExportFormat, batches: &mut mpsc::Receiver<Vec<K8sLog>>) -> std::io::Result<usize> {
+    if format == ExportFormat::Csv {
+        writeln!(out, "{}", CSV_HEADER)?;
+    }
+    let mut lines = 0;
+    while let Some(logs) = batches.blocking_recv() {
+        for log in &logs {
+            write_log(out, format, log)?;
+        }
+        lines += logs.len();
+    }
+    Ok(lines)
+}
+
+// Export the full result of a namespace log query, not just the visible page.
+// Each source is filtered and written as soon as it is fetched, so the file is
+// ordered within a source but not across sources. `gzip` defaults to whether
+// the path ends in `.gz`.
+#[tauri::command]
+pub async fn k8s_export_logs(
+    query: NamespaceLogsQuery,
+    path: String,
+    format: ExportFormat,
+    gzip: Option<bool>,
+    concurrency: Option<usize>,
+    pod_timeout_secs: Option<u64>
+) -> Result<ExportSummary, LogQueryError> {
+    let path = PathBuf::from(path);
+    let gzip = gzip.unwrap_or_else(|| path.extension().is_some_and(|ext| ext == "gz"));
+    let settings = LogFetchSettings::new(concurrency, pod_timeout_secs);
+    let sources = namespace_log_sources(&query).await?;
+
+    let (sender, mut receiver) = mpsc::channel::<Vec<K8sLog>>(EXPORT_QUEUE_BATCHES);
+    let target = path.clone();
+    let writer = tokio::task::spawn_blocking(move || {
+        let file = File::create(&target)?;
+        let lines = if gzip {
+            let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+            let lines = write_batches(&mut encoder, format, &mut receiver)?;
+            encoder.finish()?.flush()?;
+            lines
+        } else {
+            let mut writer = BufWriter::new(file);
+            let lines = write_batches(&mut writer, format, &mut receiver)?;
+            writer.flush()?;
+            lines
+        };
+        std::fs::metadata(&target).map(|m| (lines, m.len()))
+    });
+
+    let mut errors = Vec::new();
+    let mut fetches = sources.fetch(&settings);
+    while let Some((_, result)) = fetches.next().await {
+        match result {
+            Ok(mut logs) => {
+                sources.filter.apply(&mut logs);
+                // The writer only goes away on a write error, which it reports below
+                if !logs.is_empty() && sender.send(logs).await.is_err() {
+                    break;
+                }
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+    drop(fetches);
+    drop(sender);
+    errors.sort_by(|a, b| (&a.pod, &a.container, a.previous).cmp(&(&b.pod, &b.container, b.previous)));
+
+    let (lines, bytes) = writer.await
+        .map_err(|e| format!("Export failed: {}", e))?
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok(ExportSummary {
+        path: path.display().to_string(),
+        format,
+        gzip,
+        lines,
+        bytes,
+        errors,
+    })
+}
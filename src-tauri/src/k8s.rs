@@ -1,260 +1,23 @@
-use std::process::Command;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 use kube::{Client, Api, ResourceExt};
-use kube::api::{ListParams, ObjectList};
+use kube::api::{ListParams, LogParams, ObjectList};
 use k8s_openapi::api::core::v1::{Pod, Service, Namespace, ConfigMap, Secret};
-use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::apps::v1::{Deployment, ReplicaSet};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
 use anyhow::Result;
 use base64::Engine;
-use regex::Regex;
+use chrono::{DateTime, Utc};
+use futures::{AsyncBufReadExt, Stream, StreamExt, TryStreamExt};
+use tauri::{AppHandle, Emitter};
 
-// Advanced search structures
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum SearchOperator {
-    AND,
-    OR,
-    NOT,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum SearchField {
-    Message,
-    Pod,
-    Container,
-    Level,
-    Timestamp,
-    All,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum SearchPattern {
-    Contains(String),
-    Equals(String),
-    StartsWith(String),
-    EndsWith(String),
-    Regex(String),
-    GreaterThan(String),
-    LessThan(String),
-    Between(String, String),
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SearchCondition {
-    pub field: SearchField,
-    pub pattern: SearchPattern,
-    pub negated: bool,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SearchQuery {
-    pub conditions: Vec<SearchCondition>,
-    pub operator: SearchOperator,
-    pub group: Option<Box<SearchQuery>>,
-}
-
-// Search query parser
-fn parse_search_query(query: &str) -> Result<SearchQuery, String> {
-    // Simple parser for now - can be enhanced with proper parsing library
-    let query = query.trim();
-    
-    if query.is_empty() {
-        return Err("Empty search query".to_string());
-    }
-    
-    // Check for logical operators
-    if query.contains(" AND ") {
-        let parts: Vec<&str> = query.split(" AND ").collect();
-        let conditions = parts.iter()
-            .map(|part| parse_simple_condition(part))
-            .collect::<Result<Vec<_>, _>>()?;
-        
-        return Ok(SearchQuery {
-            conditions,
-            operator: SearchOperator::AND,
-            group: None,
-        });
-    }
-    
-    if query.contains(" OR ") {
-        let parts: Vec<&str> = query.split(" OR ").collect();
-        let conditions = parts.iter()
-            .map(|part| parse_simple_condition(part))
-            .collect::<Result<Vec<_>, _>>()?;
-        
-        return Ok(SearchQuery {
-            conditions,
-            operator: SearchOperator::OR,
-            group: None,
-        });
-    }
-    
-    // Single condition
-    let condition = parse_simple_condition(query)?;
-    Ok(SearchQuery {
-        conditions: vec![condition],
-        operator: SearchOperator::AND,
-        group: None,
-    })
-}
-
-fn parse_simple_condition(query: &str) -> Result<SearchCondition, String> {
-    let query = query.trim();
-    let mut negated = false;
-    let mut field = SearchField::All;
-    let mut pattern = SearchPattern::Contains(query.to_string());
-    
-    // Check for negation
-    if query.starts_with("NOT ") {
-        negated = true;
-        let query = &query[4..];
-        return parse_simple_condition(query).map(|mut condition| {
-            condition.negated = true;
-            condition
-        });
-    }
-    
-    // Check for field-specific searches
-    if query.contains(":") {
-        let parts: Vec<&str> = query.splitn(2, ':').collect();
-        if parts.len() == 2 {
-            let field_name = parts[0].trim().to_lowercase();
-            let value = parts[1].trim();
-            
-            field = match field_name.as_str() {
-                "message" | "msg" => SearchField::Message,
-                "pod" => SearchField::Pod,
-                "container" | "cont" => SearchField::Container,
-                "level" | "severity" => SearchField::Level,
-                "timestamp" | "time" => SearchField::Timestamp,
-                _ => SearchField::All,
-            };
-            
-            // Parse pattern
-            pattern = parse_pattern(value);
-        }
-    } else {
-        pattern = parse_pattern(query);
-    }
-    
-    Ok(SearchCondition {
-        field,
-        pattern,
-        negated,
-    })
-}
-
-fn parse_pattern(value: &str) -> SearchPattern {
-    let value = value.trim();
-    
-    // Check for regex pattern
-    if value.starts_with('/') && value.ends_with('/') {
-        let regex = value[1..value.len()-1].to_string();
-        return SearchPattern::Regex(regex);
-    }
-    
-    // Check for equals
-    if value.starts_with('=') {
-        return SearchPattern::Equals(value[1..].to_string());
-    }
-    
-    // Check for starts with
-    if value.starts_with('^') {
-        return SearchPattern::StartsWith(value[1..].to_string());
-    }
-    
-    // Check for ends with
-    if value.ends_with('$') {
-        return SearchPattern::EndsWith(value[..value.len()-1].to_string());
-    }
-    
-    // Check for range
-    if value.contains("..") {
-        let parts: Vec<&str> = value.split("..").collect();
-        if parts.len() == 2 {
-            return SearchPattern::Between(parts[0].to_string(), parts[1].to_string());
-        }
-    }
-    
-    // Check for comparison operators
-    if value.starts_with('>') {
-        return SearchPattern::GreaterThan(value[1..].to_string());
-    }
-    
-    if value.starts_with('<') {
-        return SearchPattern::LessThan(value[1..].to_string());
-    }
-    
-    // Default to contains
-    SearchPattern::Contains(value.to_string())
-}
-
-// Evaluate search condition against a log entry
-fn evaluate_condition(log: &K8sLog, condition: &SearchCondition) -> bool {
-    let field_value = match condition.field {
-        SearchField::Message => &log.message,
-        SearchField::Pod => &log.pod,
-        SearchField::Container => &log.container,
-        SearchField::Level => &log.level,
-        SearchField::Timestamp => &log.timestamp,
-        SearchField::All => &log.message, // Default to message for "all"
-    };
-    
-    let matches = match &condition.pattern {
-        SearchPattern::Contains(pattern) => {
-            field_value.to_lowercase().contains(&pattern.to_lowercase())
-        },
-        SearchPattern::Equals(pattern) => {
-            field_value.to_lowercase() == pattern.to_lowercase()
-        },
-        SearchPattern::StartsWith(pattern) => {
-            field_value.to_lowercase().starts_with(&pattern.to_lowercase())
-        },
-        SearchPattern::EndsWith(pattern) => {
-            field_value.to_lowercase().ends_with(&pattern.to_lowercase())
-        },
-        SearchPattern::Regex(pattern) => {
-            match Regex::new(pattern) {
-                Ok(regex) => regex.is_match(field_value),
-                Err(_) => false, // Invalid regex
-            }
-        },
-        SearchPattern::GreaterThan(pattern) => {
-            field_value > pattern
-        },
-        SearchPattern::LessThan(pattern) => {
-            field_value < pattern
-        },
-        SearchPattern::Between(start, end) => {
-            field_value >= start && field_value <= end
-        },
-    };
-    
-    if condition.negated {
-        !matches
-    } else {
-        matches
-    }
-}
-
-// Evaluate search query against a log entry
-fn evaluate_query(log: &K8sLog, query: &SearchQuery) -> bool {
-    if query.conditions.is_empty() {
-        return true;
-    }
-    
-    match query.operator {
-        SearchOperator::AND => {
-            query.conditions.iter().all(|condition| evaluate_condition(log, condition))
-        },
-        SearchOperator::OR => {
-            query.conditions.iter().any(|condition| evaluate_condition(log, condition))
-        },
-        SearchOperator::NOT => {
-            !query.conditions.iter().any(|condition| evaluate_condition(log, condition))
-        },
-    }
-}
+use crate::log_parser::{format_timestamp, parse_log_line, LogLevel};
+use crate::multiline::{assemble_multiline, MultilineAssembler};
+use crate::search::{evaluate_query, matched_fields, parse_and_compile, parse_time, CompiledQuery, SearchError};
+use crate::trace::log_matches_trace;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct K8sPod {
@@ -319,8 +82,75 @@ pub struct K8sLog {
     pub timestamp: String,
     pub level: String,
     pub message: String,
+    #[serde(default)]
+    pub namespace: String,
+    pub pod: String,
+    pub container: String,
+    // Fields parsed from JSON or logfmt lines, nested keys joined with dots
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+    // Line came from the previous (last terminated) instance of the container
+    #[serde(default)]
+    pub previous: bool,
+    // Fields that satisfied the search query, so the UI can highlight them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_fields: Vec<String>,
+}
+
+// Event emitted for every event received by a follow subscription; stack
+// trace lines are joined as they are for fetched logs
+pub const LOG_EVENT: &str = "k8s-log";
+// Event emitted when one of a subscription's streams closes
+pub const LOG_STREAM_END_EVENT: &str = "k8s-log-stream-end";
+// How long a followed stream may be quiet before its open multiline event is
+// emitted; a stack trace arrives in one burst
+const FOLLOW_MULTILINE_IDLE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct K8sLogEvent {
+    pub subscription_id: String,
+    pub log: K8sLog,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct K8sLogStreamEnd {
+    pub subscription_id: String,
     pub pod: String,
     pub container: String,
+    pub error: Option<String>,
+    // The last stream of the subscription has ended, so following has stopped
+    pub subscription_ended: bool,
+}
+
+// A follow subscription, owning one streaming task per pod/container
+struct LogSubscription {
+    handles: Vec<tokio::task::AbortHandle>,
+    // Streams still running; the last one to end removes the subscription
+    active: usize,
+}
+
+// Active follow subscriptions
+static LOG_SUBSCRIPTIONS: OnceLock<Mutex<HashMap<String, LogSubscription>>> = OnceLock::new();
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(1);
+
+fn log_subscriptions() -> &'static Mutex<HashMap<String, LogSubscription>> {
+    LOG_SUBSCRIPTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Record that one stream of a subscription ended; true when it was the last
+fn end_subscription_stream(subscription_id: &str) -> bool {
+    let Ok(mut subscriptions) = log_subscriptions().lock() else {
+        return false;
+    };
+    let Some(subscription) = subscriptions.get_mut(subscription_id) else {
+        return false;
+    };
+    subscription.active = subscription.active.saturating_sub(1);
+    if subscription.active == 0 {
+        subscriptions.remove(subscription_id);
+        return true;
+    }
+    false
 }
 
 // Kubernetes API client instance
@@ -336,7 +166,7 @@ pub async fn init_k8s_client() -> Result<()> {
 }
 
 // Get Kubernetes client
-fn get_k8s_client() -> Result<Client> {
+pub(crate) fn get_k8s_client() -> Result<Client> {
     unsafe {
         K8S_CLIENT.clone().ok_or_else(|| anyhow::anyhow!("Kubernetes client not initialized"))
     }
@@ -759,192 +589,1089 @@ pub async fn k8s_get_secrets(namespace: Option<String>) -> Result<Vec<K8sSecret>
     Ok(result)
 }
 
-// Get logs from a pod using kubectl (keeping this for now as it's more complex with API)
-#[tauri::command]
-pub async fn k8s_get_logs(namespace: String, pod: String, container: Option<String>, tail: Option<i32>) -> Result<Vec<K8sLog>, String> {
-    let result = tokio::task::spawn_blocking(move || {
-        let mut cmd = Command::new("kubectl");
-        cmd.arg("logs")
-            .arg("-n").arg(&namespace)
-            .arg(&pod);
-
-        if let Some(ref cont) = container {
-            cmd.arg("-c").arg(cont);
+// Turn a kube error into a message that keeps the API server's status and reason
+pub(crate) fn describe_kube_error(err: kube::Error) -> String {
+    match err {
+        kube::Error::Api(response) => {
+            format!("{} ({} {})", response.message, response.code, response.reason)
         }
+        other => other.to_string(),
+    }
+}
+
+fn elapsed_ms(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+// Every container declared by a pod: regular, then init, then ephemeral
+fn pod_container_names(pod: &Pod) -> Vec<String> {
+    let mut containers = Vec::new();
 
-        if let Some(t) = tail {
-            cmd.arg("--tail").arg(t.to_string());
+    if let Some(ref spec) = pod.spec {
+        containers.extend(spec.containers.iter().map(|c| c.name.clone()));
+        if let Some(ref init_containers) = spec.init_containers {
+            containers.extend(init_containers.iter().map(|c| c.name.clone()));
         }
+        if let Some(ref ephemeral_containers) = spec.ephemeral_containers {
+            containers.extend(ephemeral_containers.iter().map(|c| c.name.clone()));
+        }
+    }
+
+    containers
+}
+
+// Containers that have logs to read, with their restart counts: those that
+// are running or have run at least once. Init containers that never started
+// and ephemeral containers still waiting would only produce "waiting to start" errors.
+fn loggable_containers(pod: &Pod) -> Vec<(String, i32)> {
+    let Some(ref status) = pod.status else {
+        return Vec::new();
+    };
+
+    let statuses = status.container_statuses.iter()
+        .chain(status.init_container_statuses.iter())
+        .chain(status.ephemeral_container_statuses.iter())
+        .flatten();
 
-        let output = cmd.output()
-            .map_err(|e| format!("Failed to execute kubectl logs: {}", e))?;
+    let started: HashMap<&str, i32> = statuses
+        .filter(|cs| {
+            let has_run = |state: &Option<k8s_openapi::api::core::v1::ContainerState>| {
+                state.as_ref().is_some_and(|s| s.running.is_some() || s.terminated.is_some())
+            };
+            has_run(&cs.state) || has_run(&cs.last_state)
+        })
+        .map(|cs| (cs.name.as_str(), cs.restart_count))
+        .collect();
+
+    // Keep the spec's order so output is stable
+    pod_container_names(pod).into_iter()
+        .filter_map(|name| {
+            let restarts = *started.get(name.as_str())?;
+            Some((name, restarts))
+        })
+        .collect()
+}
 
-        if !output.status.success() {
-            return Err(format!("kubectl logs command failed: {}", String::from_utf8_lossy(&output.stderr)));
+// Pick the container kubectl would use when none is given: the
+// default-container annotation if present, otherwise the first container
+fn default_container(pod: &Pod) -> Option<String> {
+    if let Some(name) = pod.metadata.annotations.as_ref()
+        .and_then(|a| a.get("kubectl.kubernetes.io/default-container")) {
+        return Some(name.clone());
+    }
+
+    pod.spec.as_ref()
+        .and_then(|spec| spec.containers.first())
+        .map(|c| c.name.clone())
+}
+
+// One pod/container log stream to read
+#[derive(Debug, Clone)]
+struct LogTarget {
+    pod: String,
+    container: String,
+    // Read the previous (last terminated) instance of the container
+    previous: bool,
+}
+
+// Options applied to every log request made for a query
+#[derive(Debug, Clone, Default)]
+struct LogFetchOptions {
+    tail: Option<i32>,
+    since: Option<i64>,
+    since_time: Option<DateTime<Utc>>,
+    // The log API cannot stop at a time, so this is applied to parsed lines
+    until: Option<DateTime<Utc>>,
+    // Join stack trace lines into single events
+    multiline: bool,
+}
+
+impl LogFetchOptions {
+    fn new(
+        tail: Option<i32>,
+        since: Option<i64>,
+        since_time: Option<&str>,
+        until: Option<&str>,
+    ) -> Result<Self, String> {
+        let parse_bound = |name: &str, value: Option<&str>| -> Result<Option<DateTime<Utc>>, String> {
+            match value.map(str::trim).filter(|v| !v.is_empty()) {
+                Some(text) => parse_time(text, Utc::now())
+                    .map(Some)
+                    .ok_or_else(|| format!("Invalid {} '{}': expected RFC3339 or a relative time like -15m", name, text)),
+                None => Ok(None),
+            }
+        };
+
+        let since_time = parse_bound("since_time", since_time)?;
+        let until = parse_bound("until", until)?;
+        if let (Some(start), Some(end)) = (since_time, until) {
+            if start > end {
+                return Err("since_time must be before until".to_string());
+            }
         }
 
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let mut logs = Vec::new();
-
-        for line in output_str.lines() {
-            if !line.trim().is_empty() {
-                // Parse log line (this is a simplified parser)
-                logs.push(K8sLog {
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                    level: "INFO".to_string(),
-                    message: line.to_string(),
-                    pod: pod.clone(),
-                    container: container.clone().unwrap_or_else(|| "main".to_string()),
-                });
+        Ok(LogFetchOptions {
+            tail,
+            since: since.filter(|s| *s > 0),
+            since_time,
+            until,
+            multiline: true,
+        })
+    }
+
+    fn log_params(&self, container: &str, previous: bool) -> LogParams {
+        // The API accepts only one of sinceSeconds and sinceTime, so when both
+        // are given send the later of the two as a time
+        let (since_seconds, since_time) = match (self.since, self.since_time) {
+            (Some(seconds), Some(time)) => {
+                let relative = Utc::now() - chrono::Duration::seconds(seconds);
+                (None, Some(relative.max(time)))
             }
+            (seconds, time) => (seconds, time),
+        };
+
+        LogParams {
+            container: Some(container.to_string()),
+            previous,
+            tail_lines: self.tail.filter(|t| *t >= 0).map(i64::from),
+            since_seconds,
+            since_time,
+            // Kubelet timestamps give each line its real time for ordering
+            timestamps: true,
+            ..LogParams::default()
         }
+    }
+
+    // Drop lines after `until`; timestamps share one fixed-width format, so
+    // string comparison is time order
+    fn apply_until(&self, logs: &mut Vec<K8sLog>) {
+        if let Some(ref until) = self.until {
+            let until = format_timestamp(until);
+            logs.retain(|log| log.timestamp <= until);
+        }
+    }
+}
+
+// Fetch and parse one container's logs
+async fn fetch_container_logs(
+    api: &Api<Pod>,
+    namespace: &str,
+    pod: &str,
+    container: &str,
+    previous: bool,
+    options: &LogFetchOptions,
+) -> Result<Vec<K8sLog>, LogSourceError> {
+    let output = api.logs(pod, &options.log_params(container, previous)).await
+        .map_err(|e| LogSourceError::from_kube(pod, Some(container), previous, e))?;
+
+    let mut logs: Vec<K8sLog> = output.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| K8sLog {
+            previous,
+            ..parse_log_line(line, namespace, pod, container)
+        })
+        .collect();
+    // Join before `until` and any filtering so a trace is kept or dropped whole
+    if options.multiline {
+        logs = assemble_multiline(logs);
+    }
+    options.apply_until(&mut logs);
 
-        Ok(logs)
-    }).await.map_err(|e| format!("Task failed: {}", e))?;
+    Ok(logs)
+}
 
-    result
+// What to fetch for a single pod's logs
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PodLogsQuery {
+    pub namespace: String,
+    pub pod: String,
+    // Defaults to the container kubectl would pick
+    pub container: Option<String>,
+    pub tail: Option<i32>,
+    pub since: Option<i64>,
+    pub since_time: Option<String>,
+    pub until: Option<String>,
+    // Read only the previous (last terminated) instance
+    pub previous: Option<bool>,
+    // Merge in the previous instance of a restarted container; on unless set to false
+    pub include_previous: Option<bool>,
+    // Join stack trace lines into single events; on unless set to false
+    pub multiline: Option<bool>,
 }
 
-// Get logs from namespace with optional filtering by deployments or pods
+// Result of k8s_get_logs. A previous instance that could not be read is
+// reported in `errors`; the current instance's logs are still returned.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PodLogsResponse {
+    pub logs: Vec<K8sLog>,
+    pub errors: Vec<LogSourceError>,
+}
+
+// Get logs from a pod using the Kubernetes API. A restarted container's
+// previous instance is merged in, as k8s_get_namespace_logs does.
 #[tauri::command]
-pub async fn k8s_get_namespace_logs(
-    namespace: String, 
-    deployments: Option<Vec<String>>, 
-    pods: Option<Vec<String>>, 
+pub async fn k8s_get_logs(query: PodLogsQuery) -> Result<PodLogsResponse, String> {
+    let PodLogsQuery {
+        namespace, pod, container, tail, since, since_time, until,
+        previous, include_previous, multiline,
+    } = query;
+    let mut options = LogFetchOptions::new(tail, since, since_time.as_deref(), until.as_deref())?;
+    options.multiline = multiline.unwrap_or(true);
+    let only_previous = previous.unwrap_or(false);
+    let include_previous = !only_previous && include_previous.unwrap_or(true);
+    let client = get_k8s_client().map_err(|e| e.to_string())?;
+    let api: Api<Pod> = Api::namespaced(client, &namespace);
+
+    // The pod is needed for its default container and restart counts
+    let pod_obj = if container.is_none() || include_previous {
+        Some(api.get(&pod).await
+            .map_err(|e| format!("Failed to get pod {}: {}", pod, describe_kube_error(e)))?)
+    } else {
+        None
+    };
+
+    // Resolve the container up front so every line is tagged with its real name
+    let container = match container {
+        Some(name) => name,
+        None => pod_obj.as_ref()
+            .and_then(default_container)
+            .ok_or_else(|| format!("Pod {} has no containers", pod))?,
+    };
+
+    let restarted = pod_obj.as_ref().is_some_and(|pod_obj| {
+        loggable_containers(pod_obj).iter().any(|(name, restarts)| *name == container && *restarts > 0)
+    });
+
+    let mut logs = fetch_container_logs(&api, &namespace, &pod, &container, only_previous, &options).await
+        .map_err(|e| e.to_string())?;
+
+    let mut errors = Vec::new();
+    if include_previous && restarted {
+        // The previous instance may already be gone; the current logs still stand
+        match fetch_container_logs(&api, &namespace, &pod, &container, true, &options).await {
+            Ok(mut previous_logs) => {
+                logs.append(&mut previous_logs);
+                logs.sort_by(|a, b| log_position(a).cmp(&log_position(b)));
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+
+    Ok(PodLogsResponse { logs, errors })
+}
+
+// Stream one pod/container and forward each line to the webview until the
+// connection closes or the subscription is cancelled
+async fn follow_container_logs(
+    app: AppHandle,
+    api: Api<Pod>,
+    subscription_id: String,
+    namespace: String,
+    pod: String,
+    container: String,
     tail: Option<i32>,
-    page: Option<i32>,
-    search: Option<String>,
-    severity: Option<String>,
-    trace_id: Option<String>
-) -> Result<Vec<K8sLog>, String> {
+) {
+    let lp = LogParams {
+        container: Some(container.clone()),
+        follow: true,
+        timestamps: true,
+        tail_lines: Some(tail.filter(|t| *t >= 0).map(i64::from).unwrap_or(0)),
+        ..LogParams::default()
+    };
+
+    let result = async {
+        let stream = api.log_stream(&pod, &lp).await
+            .map_err(|e| format!("Failed to follow logs for pod {} container {}: {}", pod, container, describe_kube_error(e)))?;
+        let mut lines = stream.lines();
+        let mut assembler = MultilineAssembler::new();
+        let emit = |log: K8sLog| {
+            let event = K8sLogEvent { subscription_id: subscription_id.clone(), log };
+            app.emit(LOG_EVENT, event)
+                .map_err(|e| format!("Failed to emit log event: {}", e))
+        };
+
+        loop {
+            // Only wait with a deadline while an event is open, so an idle stream sleeps
+            let next = if assembler.is_open() {
+                match tokio::time::timeout(FOLLOW_MULTILINE_IDLE, lines.try_next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        if let Some(log) = assembler.finish() {
+                            emit(log)?;
+                        }
+                        continue;
+                    }
+                }
+            } else {
+                lines.try_next().await
+            };
+            let line = match next {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) => {
+                    if let Some(log) = assembler.finish() {
+                        emit(log)?;
+                    }
+                    return Err(format!("Log stream for pod {} container {} failed: {}", pod, container, e));
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(log) = assembler.push(parse_log_line(&line, &namespace, &pod, &container)) {
+                emit(log)?;
+            }
+        }
+        if let Some(log) = assembler.finish() {
+            emit(log)?;
+        }
+
+        Ok::<(), String>(())
+    }.await;
+
+    let subscription_ended = end_subscription_stream(&subscription_id);
+    let end = K8sLogStreamEnd {
+        subscription_id,
+        pod,
+        container,
+        error: result.err(),
+        subscription_ended,
+    };
+    let _ = app.emit(LOG_STREAM_END_EVENT, end);
+}
+
+// Start following logs for one or more pods; lines arrive as `k8s-log` events
+#[tauri::command]
+pub async fn k8s_follow_logs(
+    app: AppHandle,
+    namespace: String,
+    pods: Vec<String>,
+    container: Option<String>,
+    tail: Option<i32>
+) -> Result<String, String> {
+    if pods.is_empty() {
+        return Err("At least one pod is required".to_string());
+    }
+
     let client = get_k8s_client().map_err(|e| e.to_string())?;
     let api: Api<Pod> = Api::namespaced(client, &namespace);
+
+    // Resolve every source before starting so a bad pod fails the whole request
+    let mut sources = Vec::new();
+    for pod in pods {
+        let container = match container {
+            Some(ref name) => name.clone(),
+            None => {
+                let pod_obj = api.get(&pod).await
+                    .map_err(|e| format!("Failed to get pod {}: {}", pod, describe_kube_error(e)))?;
+                default_container(&pod_obj)
+                    .ok_or_else(|| format!("Pod {} has no containers", pod))?
+            }
+        };
+        sources.push((pod, container));
+    }
+
+    let subscription_id = format!("logs-{}", NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed));
+    // Registered before the tasks start so one that ends at once still finds it
+    log_subscriptions().lock()
+        .map_err(|_| "Log subscriptions lock poisoned".to_string())?
+        .insert(subscription_id.clone(), LogSubscription { handles: Vec::new(), active: sources.len() });
+
+    let mut handles = Vec::new();
+    for (pod, container) in sources {
+        let task = tokio::spawn(follow_container_logs(
+            app.clone(),
+            api.clone(),
+            subscription_id.clone(),
+            namespace.clone(),
+            pod,
+            container,
+            tail,
+        ));
+        handles.push(task.abort_handle());
+    }
+
+    // Already gone when every stream ended before this point
+    if let Some(subscription) = log_subscriptions().lock()
+        .map_err(|_| "Log subscriptions lock poisoned".to_string())?
+        .get_mut(&subscription_id) {
+        subscription.handles = handles;
+    }
+
+    Ok(subscription_id)
+}
+
+// Stop a follow subscription started with k8s_follow_logs
+#[tauri::command]
+pub async fn k8s_unfollow_logs(subscription_id: String) -> Result<(), String> {
+    // Unknown when its streams have all ended on their own, which is not an error
+    let Some(subscription) = log_subscriptions().lock()
+        .map_err(|_| "Log subscriptions lock poisoned".to_string())?
+        .remove(&subscription_id) else {
+        return Ok(());
+    };
+
+    for handle in subscription.handles {
+        handle.abort();
+    }
+
+    Ok(())
+}
+
+// Error returned by log queries: either the search could not be parsed or
+// compiled, or the request itself failed
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "kind", content = "detail")]
+pub enum LogQueryError {
+    InvalidQuery(SearchError),
+    Request(String),
+}
+
+impl From<String> for LogQueryError {
+    fn from(message: String) -> Self {
+        LogQueryError::Request(message)
+    }
+}
+
+// Containers fetched at once by k8s_get_namespace_logs unless the caller says otherwise
+const DEFAULT_LOG_FETCH_CONCURRENCY: usize = 8;
+// How long a single container's log request may take before it is reported as failed
+const DEFAULT_POD_LOG_TIMEOUT_SECS: u64 = 30;
+
+// A pod/container whose logs could not be fetched. `reason` and `status` come
+// from the API server when it answered (e.g. "Forbidden", 403).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogSourceError {
+    pub pod: String,
+    pub container: Option<String>,
+    pub previous: bool,
+    pub reason: String,
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+impl LogSourceError {
+    fn from_kube(pod: &str, container: Option<&str>, previous: bool, err: kube::Error) -> Self {
+        let (reason, status, message) = match err {
+            kube::Error::Api(response) => (response.reason, Some(response.code), response.message),
+            other => ("RequestFailed".to_string(), None, other.to_string()),
+        };
+
+        LogSourceError {
+            pod: pod.to_string(),
+            container: container.map(str::to_string),
+            previous,
+            reason,
+            status,
+            message,
+        }
+    }
+
+    fn timed_out(pod: &str, container: Option<&str>, previous: bool, timeout: Duration) -> Self {
+        LogSourceError {
+            pod: pod.to_string(),
+            container: container.map(str::to_string),
+            previous,
+            reason: "Timeout".to_string(),
+            status: None,
+            message: format!("Timed out after {}s", timeout.as_secs()),
+        }
+    }
+}
+
+impl std::fmt::Display for LogSourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to get logs for pod {}", self.pod)?;
+        if let Some(ref container) = self.container {
+            write!(f, " container {}", container)?;
+        }
+        if self.previous {
+            write!(f, " (previous instance)")?;
+        }
+        match self.status {
+            Some(status) => write!(f, ": {} ({} {})", self.message, status, self.reason),
+            None => write!(f, ": {} ({})", self.message, self.reason),
+        }
+    }
+}
+
+// How long one pod/container took and how many lines it returned
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogSourceTiming {
+    pub pod: String,
+    pub container: Option<String>,
+    pub previous: bool,
+    pub duration_ms: u64,
+    pub lines: usize,
+    pub failed: bool,
+}
+
+// Where the time went for a namespace query
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LogQueryTiming {
+    pub list_ms: u64,
+    pub fetch_ms: u64,
+    pub filter_ms: u64,
+    pub total_ms: u64,
+    pub sources: Vec<LogSourceTiming>,
+}
+
+// Result of k8s_get_namespace_logs: the merged logs plus everything needed to
+// tell the user the view is incomplete, e.g. "3 of 40 pods failed: Forbidden"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NamespaceLogsResponse {
+    pub logs: Vec<K8sLog>,
+    pub errors: Vec<LogSourceError>,
+    pub sources_total: usize,
+    pub sources_failed: usize,
+    pub timing: LogQueryTiming,
+    // Matching lines across all pages
+    pub total: usize,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+// What to fetch and how to filter it for a namespace log query. Its JSON form
+// is also the key of the result cache, so equal queries share one snapshot.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct NamespaceLogsQuery {
+    pub namespace: String,
+    pub deployments: Option<Vec<String>>,
+    pub pods: Option<Vec<String>>,
+    pub tail: Option<i32>,
+    pub search: Option<String>,
+    pub severity: Option<String>,
+    pub trace_id: Option<String>,
+    pub include_previous: Option<bool>,
+    pub since: Option<i64>,
+    pub since_time: Option<String>,
+    pub until: Option<String>,
+    // Join stack trace lines into single events; on unless set to false
+    pub multiline: Option<bool>,
+}
+
+// How hard to hit the API server while collecting a namespace query
+#[derive(Debug, Clone)]
+pub struct LogFetchSettings {
+    pub concurrency: usize,
+    pub pod_timeout: Duration,
+}
+
+impl LogFetchSettings {
+    pub fn new(concurrency: Option<usize>, pod_timeout_secs: Option<u64>) -> Self {
+        LogFetchSettings {
+            concurrency: concurrency.filter(|c| *c > 0).unwrap_or(DEFAULT_LOG_FETCH_CONCURRENCY),
+            pod_timeout: Duration::from_secs(pod_timeout_secs.filter(|t| *t > 0).unwrap_or(DEFAULT_POD_LOG_TIMEOUT_SECS)),
+        }
+    }
+}
+
+// Every log matching a namespace query, sorted by `log_position`
+#[derive(Debug, Clone)]
+pub struct CollectedLogs {
+    pub logs: Vec<K8sLog>,
+    pub errors: Vec<LogSourceError>,
+    pub sources_total: usize,
+    pub timing: LogQueryTiming,
+}
+
+// Total order of aggregated logs: time first, then the source as a tie-breaker
+pub(crate) fn log_position(log: &K8sLog) -> (&str, &str, &str, bool) {
+    (&log.timestamp, &log.pod, &log.container, log.previous)
+}
+
+// Page size used when the caller asks for a cursor but no page size
+const DEFAULT_LOG_PAGE_SIZE: usize = 50;
+// How long a collected result is reused for paging before it is fetched again
+const LOG_QUERY_CACHE_TTL: Duration = Duration::from_secs(300);
+// Distinct queries kept in the cache; the oldest is dropped first
+const LOG_QUERY_CACHE_ENTRIES: usize = 16;
+
+struct CachedLogQuery {
+    snapshot: u64,
+    created: Instant,
+    result: Arc<CollectedLogs>,
+}
+
+static LOG_QUERY_CACHE: OnceLock<Mutex<HashMap<String, CachedLogQuery>>> = OnceLock::new();
+static NEXT_SNAPSHOT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn log_query_cache() -> &'static Mutex<HashMap<String, CachedLogQuery>> {
+    LOG_QUERY_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached_log_query(key: &str) -> Result<Option<(u64, Arc<CollectedLogs>)>, String> {
+    let mut cache = log_query_cache().lock()
+        .map_err(|_| "Log query cache lock poisoned".to_string())?;
+    cache.retain(|_, entry| entry.created.elapsed() < LOG_QUERY_CACHE_TTL);
+    Ok(cache.get(key).map(|entry| (entry.snapshot, entry.result.clone())))
+}
+
+fn cache_log_query(key: String, result: Arc<CollectedLogs>) -> Result<u64, String> {
+    let snapshot = NEXT_SNAPSHOT_ID.fetch_add(1, Ordering::Relaxed);
+    let mut cache = log_query_cache().lock()
+        .map_err(|_| "Log query cache lock poisoned".to_string())?;
+    while cache.len() >= LOG_QUERY_CACHE_ENTRIES && !cache.contains_key(&key) {
+        let oldest = cache.iter()
+            .min_by_key(|(_, entry)| entry.created)
+            .map(|(key, _)| key.clone());
+        match oldest {
+            Some(oldest) => cache.remove(&oldest),
+            None => break,
+        };
+    }
+    cache.insert(key, CachedLogQuery { snapshot, created: Instant::now(), result });
+    Ok(snapshot)
+}
+
+// The cached result for a query, collecting it first when missing, expired
+// or when `refresh` is set. Returns the snapshot id with the result.
+pub async fn cached_namespace_logs(
+    query: &NamespaceLogsQuery,
+    settings: &LogFetchSettings,
+    refresh: bool,
+) -> Result<(u64, Arc<CollectedLogs>), LogQueryError> {
+    let key = serde_json::to_string(query).map_err(|e| e.to_string())?;
+    if !refresh {
+        if let Some(hit) = cached_log_query(&key)? {
+            return Ok(hit);
+        }
+    }
+    let result = Arc::new(collect_namespace_logs(query, settings).await?);
+    Ok((cache_log_query(key, result.clone())?, result))
+}
+
+// Opaque page token: the first log of the page it opens. The offset is only
+// trusted while the same snapshot is cached; otherwise the position is found
+// again from the timestamp and source, so paging survives a re-fetch.
+#[derive(Debug, Serialize, Deserialize)]
+struct LogCursor {
+    snapshot: u64,
+    timestamp: String,
+    pod: String,
+    container: String,
+    previous: bool,
+    offset: usize,
+}
+
+impl LogCursor {
+    fn at(snapshot: u64, logs: &[K8sLog], offset: usize) -> Self {
+        let log = &logs[offset];
+        LogCursor {
+            snapshot,
+            timestamp: log.timestamp.clone(),
+            pod: log.pod.clone(),
+            container: log.container.clone(),
+            previous: log.previous,
+            offset,
+        }
+    }
+
+    fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    // A token that is not one of ours is a bad query, not a failed request
+    fn decode(token: &str) -> Result<Self, LogQueryError> {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(token)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| LogQueryError::InvalidQuery(SearchError::new("Invalid log cursor", 0)))
+    }
+
+    fn position(&self) -> (&str, &str, &str, bool) {
+        (&self.timestamp, &self.pod, &self.container, self.previous)
+    }
+
+    // Index of the first log at or after this cursor
+    fn locate(&self, snapshot: u64, logs: &[K8sLog]) -> usize {
+        if self.snapshot == snapshot
+            && logs.get(self.offset).is_some_and(|log| log_position(log) == self.position())
+        {
+            return self.offset;
+        }
+        logs.partition_point(|log| log_position(log) < self.position())
+    }
+}
+
+// One page of a collected result with the tokens of its neighbours
+struct LogPage {
+    start: usize,
+    end: usize,
+    next_cursor: Option<String>,
+    prev_cursor: Option<String>,
+}
+
+fn log_page(snapshot: u64, logs: &[K8sLog], cursor: Option<&LogCursor>, page_size: Option<usize>) -> LogPage {
+    let total = logs.len();
+    let page_size = page_size
+        .filter(|size| *size > 0)
+        .or(cursor.map(|_| DEFAULT_LOG_PAGE_SIZE));
+    let start = cursor.map(|c| c.locate(snapshot, logs)).unwrap_or(0).min(total);
+    let end = page_size.map(|size| (start + size).min(total)).unwrap_or(total);
+
+    let next_cursor = (end < total).then(|| LogCursor::at(snapshot, logs, end).encode());
+    let prev_cursor = match page_size {
+        Some(size) if start > 0 => Some(LogCursor::at(snapshot, logs, start.saturating_sub(size)).encode()),
+        _ => None,
+    };
+    LogPage { start, end, next_cursor, prev_cursor }
+}
+
+// Check a search query without running it, so the UI can flag errors as the user types
+#[tauri::command]
+pub async fn k8s_validate_search_query(query: String) -> Result<(), SearchError> {
+    parse_and_compile(&query).map(|_| ())
+}
+
+// Check a pod's labels against a workload's label selector
+fn selector_matches(selector: &LabelSelector, labels: &BTreeMap<String, String>) -> bool {
+    let labels_match = selector.match_labels.as_ref()
+        .map(|required| required.iter().all(|(key, value)| labels.get(key) == Some(value)))
+        .unwrap_or(true);
+
+    let expressions_match = selector.match_expressions.as_ref()
+        .map(|expressions| expressions.iter().all(|expr| {
+            let value = labels.get(&expr.key);
+            let values = expr.values.as_deref().unwrap_or_default();
+            match expr.operator.as_str() {
+                "In" => value.is_some_and(|v| values.contains(v)),
+                "NotIn" => !value.is_some_and(|v| values.contains(v)),
+                "Exists" => value.is_some(),
+                "DoesNotExist" => value.is_none(),
+                _ => false,
+            }
+        }))
+        .unwrap_or(true);
+
+    // An empty selector selects nothing for our purposes
+    let has_terms = selector.match_labels.as_ref().is_some_and(|l| !l.is_empty())
+        || selector.match_expressions.as_ref().is_some_and(|e| !e.is_empty());
+
+    has_terms && labels_match && expressions_match
+}
+
+// Which pods belong to a set of named workloads. Pods are matched through
+// their controller ownerReferences (Deployment -> ReplicaSet -> Pod, or a
+// StatefulSet/DaemonSet directly); pods with no controller, or one of another
+// kind, fall back to the Deployment's `spec.selector`.
+pub(crate) struct WorkloadMembership {
+    names: Vec<String>,
+    // ReplicaSet name -> owning Deployment name, for the requested deployments.
+    // None when ReplicaSets cannot be listed, e.g. RBAC allows only pods and
+    // deployments; ReplicaSet-owned pods are then matched by selector.
+    replica_sets: Option<HashMap<String, String>>,
+    selectors: Vec<LabelSelector>,
+}
+
+impl WorkloadMembership {
+    pub(crate) async fn resolve(namespace: &str, names: &[String]) -> Result<Self, String> {
+        let client = get_k8s_client().map_err(|e| e.to_string())?;
+        let rs_api: Api<ReplicaSet> = Api::namespaced(client.clone(), namespace);
+        let deployment_api: Api<Deployment> = Api::namespaced(client, namespace);
+
+        let replica_set_list = match rs_api.list(&ListParams::default()).await {
+            Ok(list) => Some(list),
+            Err(kube::Error::Api(response)) if response.code == 403 => None,
+            Err(e) => return Err(format!("Failed to list ReplicaSets in namespace {}: {}", namespace, describe_kube_error(e))),
+        };
+        let replica_sets = replica_set_list.map(|list| deployment_replica_sets(list, names));
+
+        let deployment_list = deployment_api.list(&ListParams::default()).await
+            .map_err(|e| format!("Failed to list deployments in namespace {}: {}", namespace, describe_kube_error(e)))?;
+        let selectors = deployment_list.into_iter()
+            .filter(|d| names.contains(&d.name_any()))
+            .filter_map(|d| d.spec.map(|spec| spec.selector))
+            .collect();
+
+        Ok(WorkloadMembership {
+            names: names.to_vec(),
+            replica_sets,
+            selectors,
+        })
+    }
+
+    pub(crate) fn contains(&self, pod: &Pod) -> bool {
+        let controller = pod.metadata.owner_references.as_ref()
+            .and_then(|refs| refs.iter().find(|r| r.controller == Some(true)));
+
+        if let Some(owner) = controller {
+            match (owner.kind.as_str(), &self.replica_sets) {
+                ("ReplicaSet", Some(replica_sets)) => return replica_sets.contains_key(&owner.name),
+                ("StatefulSet" | "DaemonSet", _) => return self.names.contains(&owner.name),
+                _ => {}
+            }
+        }
+
+        let empty = BTreeMap::new();
+        let labels = pod.metadata.labels.as_ref().unwrap_or(&empty);
+        self.selectors.iter().any(|selector| selector_matches(selector, labels))
+    }
+}
+
+// ReplicaSet name -> owning Deployment name, for ReplicaSets controlled by one of `names`
+fn deployment_replica_sets(replica_sets: impl IntoIterator<Item = ReplicaSet>, names: &[String]) -> HashMap<String, String> {
+    let mut owners = HashMap::new();
+    for rs in replica_sets {
+        let owner = rs.metadata.owner_references.as_ref()
+            .and_then(|refs| refs.iter().find(|r| r.controller == Some(true) && r.kind == "Deployment"));
+        if let Some(owner) = owner {
+            if names.contains(&owner.name) {
+                owners.insert(rs.name_any(), owner.name.clone());
+            }
+        }
+    }
+    owners
+}
+
+// Search, severity and trace filters of a namespace query, applied to lines
+// after they are fetched
+pub struct LogLineFilter {
+    search: Option<CompiledQuery>,
+    severity: Option<String>,
+    trace_id: Option<String>,
+}
+
+impl LogLineFilter {
+    fn new(query: &NamespaceLogsQuery) -> Result<Self, LogQueryError> {
+        let search = match query.search {
+            Some(ref search_query) if !search_query.trim().is_empty() => {
+                Some(parse_and_compile(search_query).map_err(LogQueryError::InvalidQuery)?)
+            }
+            _ => None,
+        };
+        // Accept aliases such as "warn" or "err" for the normalised levels
+        let severity = query.severity.as_deref()
+            .filter(|severity| !severity.trim().is_empty())
+            .map(|severity| LogLevel::from_name(severity)
+                .map(|level| level.as_str().to_lowercase())
+                .unwrap_or_else(|| severity.to_lowercase()));
+        let trace_id = query.trace_id.clone().filter(|trace_id| !trace_id.trim().is_empty());
+        Ok(LogLineFilter { search, severity, trace_id })
+    }
+
+    // Keep the matching lines, noting which fields the search matched
+    pub fn apply(&self, logs: &mut Vec<K8sLog>) {
+        if let Some(ref query) = self.search {
+            logs.retain(|log| evaluate_query(log, query));
+            for log in logs.iter_mut() {
+                log.matched_fields = matched_fields(log, query);
+            }
+        }
+        if let Some(ref severity) = self.severity {
+            logs.retain(|log| log.level.to_lowercase() == *severity);
+        }
+        // Reads traceparent, B3 and trace ID fields
+        if let Some(ref trace_id) = self.trace_id {
+            logs.retain(|log| log_matches_trace(log, trace_id));
+        }
+    }
+}
+
+// The containers a namespace query reads, resolved but not yet fetched
+pub struct NamespaceLogSources {
+    pub filter: LogLineFilter,
+    pub list_ms: u64,
+    started: Instant,
+    api: Api<Pod>,
+    namespace: String,
+    options: LogFetchOptions,
+    targets: Vec<LogTarget>,
+}
+
+impl NamespaceLogSources {
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    // Fetch every source, bounded so large namespaces don't flood the API
+    // server. Each source's unfiltered lines are yielded as soon as it finishes.
+    pub fn fetch<'a>(
+        &'a self,
+        settings: &LogFetchSettings,
+    ) -> impl Stream<Item = (LogSourceTiming, Result<Vec<K8sLog>, LogSourceError>)> + 'a {
+        let pod_timeout = settings.pod_timeout;
+        futures::stream::iter(self.targets.iter().cloned())
+            .map(move |target| async move {
+                let source_started = Instant::now();
+                let result = tokio::time::timeout(
+                    pod_timeout,
+                    fetch_container_logs(&self.api, &self.namespace, &target.pod, &target.container, target.previous, &self.options),
+                ).await
+                .unwrap_or_else(|_| Err(LogSourceError::timed_out(&target.pod, Some(&target.container), target.previous, pod_timeout)));
+                let timing = LogSourceTiming {
+                    pod: target.pod,
+                    container: Some(target.container),
+                    previous: target.previous,
+                    duration_ms: elapsed_ms(source_started),
+                    lines: result.as_ref().map(|logs| logs.len()).unwrap_or(0),
+                    failed: result.is_err(),
+                };
+                (timing, result)
+            })
+            .buffer_unordered(settings.concurrency)
+    }
+}
+
+// List the pods of a namespace query and pick the containers to read. The
+// search is compiled first so a bad query fails before anything is fetched.
+pub async fn namespace_log_sources(query: &NamespaceLogsQuery) -> Result<NamespaceLogSources, LogQueryError> {
+    let NamespaceLogsQuery {
+        namespace, deployments, pods, tail, include_previous, since, since_time, until, multiline, ..
+    } = query;
+    let filter = LogLineFilter::new(query)?;
+    let mut options = LogFetchOptions::new(*tail, *since, since_time.as_deref(), until.as_deref())?;
+    options.multiline = multiline.unwrap_or(true);
+    // Previous-instance logs are included for restarted containers unless turned off
+    let include_previous = include_previous.unwrap_or(true);
+
+    let started = Instant::now();
+    let client = get_k8s_client().map_err(|e| e.to_string())?;
+    let api: Api<Pod> = Api::namespaced(client, namespace);
     
     // Get all pods in the namespace
     let pods_list = api.list(&ListParams::default()).await
-        .map_err(|e| format!("Failed to get pods in namespace {}: {}", namespace, e))?;
+        .map_err(|e| format!("Failed to get pods in namespace {}: {}", namespace, describe_kube_error(e)))?;
     
-    let mut all_logs = Vec::new();
+    // Resolve which pods the requested deployments own
+    let workload_membership = match deployments {
+        Some(names) if !names.is_empty() => Some(WorkloadMembership::resolve(namespace, names).await?),
+        _ => None,
+    };
+    let list_ms = elapsed_ms(started);
+    
+    let mut targets = Vec::new();
     
     for pod_obj in pods_list {
         let pod_name = pod_obj.name_any();
         
         // Filter by specific pods if provided
-        if let Some(ref pod_filter) = pods {
+        if let Some(pod_filter) = pods {
             if !pod_filter.contains(&pod_name) {
                 continue;
             }
         }
         
         // Filter by deployments if provided
-        if let Some(ref deployment_filter) = deployments {
-            let mut matches_deployment = false;
-            
-            // Check if pod belongs to any of the specified deployments
-            if let Some(ref labels) = pod_obj.metadata.labels {
-                if let Some(app_label) = labels.get("app") {
-                    if deployment_filter.contains(app_label) {
-                        matches_deployment = true;
-                    }
-                }
-                // Also check for deployment name in labels
-                if let Some(deployment_label) = labels.get("app.kubernetes.io/name") {
-                    if deployment_filter.contains(deployment_label) {
-                        matches_deployment = true;
-                    }
-                }
-            }
-            
-            // If no deployment labels match, try to extract deployment name from pod name
-            if !matches_deployment {
-                // Common pattern: deployment-name-replicaset-pod-id
-                let parts: Vec<&str> = pod_name.split('-').collect();
-                if parts.len() >= 3 {
-                    let deployment_part = parts[..parts.len()-2].join("-");
-                    if deployment_filter.contains(&deployment_part) {
-                        matches_deployment = true;
-                    }
-                }
-            }
-            
-            if !matches_deployment {
+        if let Some(ref membership) = workload_membership {
+            if !membership.contains(&pod_obj) {
                 continue;
             }
         }
         
-        // Get logs for this pod
-        match k8s_get_logs(namespace.clone(), pod_name.clone(), None, tail).await {
-            Ok(mut pod_logs) => {
-                all_logs.append(&mut pod_logs);
-            }
-            Err(e) => {
-                // Log error but continue with other pods
-                println!("Failed to get logs for pod {}: {}", pod_name, e);
-            }
-        }
-    }
-    
-    // Sort logs by timestamp
-    all_logs.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-    
-    // Apply advanced text search if provided
-    if let Some(ref search_query) = search {
-        if !search_query.trim().is_empty() {
-            match parse_search_query(search_query) {
-                Ok(query) => {
-                    all_logs.retain(|log| evaluate_query(log, &query));
-                },
-                Err(e) => {
-                    // Fallback to simple search if parsing fails
-                    let search_lower = search_query.to_lowercase();
-                    all_logs.retain(|log| {
-                        log.message.to_lowercase().contains(&search_lower) ||
-                        log.pod.to_lowercase().contains(&search_lower) ||
-                        log.container.to_lowercase().contains(&search_lower) ||
-                        log.level.to_lowercase().contains(&search_lower)
-                    });
-                }
+        // Every container that has run, so sidecars and init containers are included
+        for (container, restarts) in loggable_containers(&pod_obj) {
+            // A restarted container's last terminated instance usually holds the crash
+            if include_previous && restarts > 0 {
+                targets.push(LogTarget {
+                    pod: pod_name.clone(),
+                    container: container.clone(),
+                    previous: true,
+                });
             }
-        }
-    }
-    
-    // Apply severity filter if provided
-    if let Some(ref severity_filter) = severity {
-        if !severity_filter.trim().is_empty() {
-            let severity_lower = severity_filter.to_lowercase();
-            all_logs.retain(|log| {
-                log.level.to_lowercase() == severity_lower
-            });
-        }
-    }
-    
-    // Apply trace ID filter if provided
-    if let Some(ref trace_id_filter) = trace_id {
-        if !trace_id_filter.trim().is_empty() {
-            let trace_id_lower = trace_id_filter.to_lowercase();
-            all_logs.retain(|log| {
-                log.message.to_lowercase().contains(&trace_id_lower)
+            targets.push(LogTarget {
+                pod: pod_name.clone(),
+                container,
+                previous: false,
             });
         }
     }
+
+    Ok(NamespaceLogSources {
+        filter,
+        list_ms,
+        started,
+        api,
+        namespace: namespace.clone(),
+        options,
+        targets,
+    })
+}
+
+// Collect the merged, sorted and filtered logs for a namespace query. Shared by
+// the paged command and anything else that needs the whole result set.
+pub async fn collect_namespace_logs(
+    query: &NamespaceLogsQuery,
+    settings: &LogFetchSettings,
+) -> Result<CollectedLogs, LogQueryError> {
+    let sources = namespace_log_sources(query).await?;
+    let sources_total = sources.len();
+    let fetch_started = Instant::now();
+    let results: Vec<_> = sources.fetch(settings).collect().await;
+    let fetch_ms = elapsed_ms(fetch_started);
     
-    // Apply pagination if page is specified
-    if let Some(page_num) = page {
-        let page_size = tail.unwrap_or(50) as usize;
-        let start_index = (page_num - 1) as usize * page_size;
-        let end_index = std::cmp::min(start_index + page_size, all_logs.len());
-        
-        if start_index < all_logs.len() {
-            all_logs = all_logs[start_index..end_index].to_vec();
-        } else {
-            all_logs.clear();
+    let mut all_logs = Vec::new();
+    let mut errors = Vec::new();
+    let mut source_timings = Vec::new();
+    for (timing, result) in results {
+        match result {
+            Ok(mut pod_logs) => all_logs.append(&mut pod_logs),
+            // Keep going with the other pods and report the failure to the caller
+            Err(error) => errors.push(error),
         }
+        source_timings.push(timing);
     }
-    
-    Ok(all_logs)
+    errors.sort_by(|a, b| (&a.pod, &a.container, a.previous).cmp(&(&b.pod, &b.container, b.previous)));
+    source_timings.sort_by(|a, b| (&a.pod, &a.container, a.previous).cmp(&(&b.pod, &b.container, b.previous)));
+    
+    let filter_started = Instant::now();
+    
+    // Sort logs by timestamp, then source, so the order is stable for cursors
+    all_logs.sort_by(|a, b| log_position(a).cmp(&log_position(b)));
+    sources.filter.apply(&mut all_logs);
+    
+    Ok(CollectedLogs {
+        logs: all_logs,
+        errors,
+        sources_total,
+        timing: LogQueryTiming {
+            list_ms: sources.list_ms,
+            fetch_ms,
+            filter_ms: elapsed_ms(filter_started),
+            total_ms: elapsed_ms(sources.started),
+            sources: source_timings,
+        },
+    })
+}
+
+// Get logs from namespace with optional filtering by deployments or pods. The
+// full result is cached per query so `cursor` pages through a stable snapshot;
+// `refresh` fetches again. Without a cursor or page size every log is returned.
+#[tauri::command]
+pub async fn k8s_get_namespace_logs(
+    query: NamespaceLogsQuery,
+    cursor: Option<String>,
+    page_size: Option<usize>,
+    refresh: Option<bool>,
+    concurrency: Option<usize>,
+    pod_timeout_secs: Option<u64>
+) -> Result<NamespaceLogsResponse, LogQueryError> {
+    let cursor = cursor.as_deref().map(LogCursor::decode).transpose()?;
+    let settings = LogFetchSettings::new(concurrency, pod_timeout_secs);
+    let (snapshot, result) = cached_namespace_logs(&query, &settings, refresh.unwrap_or(false)).await?;
+
+    let page = log_page(snapshot, &result.logs, cursor.as_ref(), page_size);
+
+    Ok(NamespaceLogsResponse {
+        logs: result.logs[page.start..page.end].to_vec(),
+        errors: result.errors.clone(),
+        sources_total: result.sources_total,
+        sources_failed: result.errors.len(),
+        timing: result.timing.clone(),
+        total: result.logs.len(),
+        next_cursor: page.next_cursor,
+        prev_cursor: page.prev_cursor,
+    })
 }
 
-// Get containers for a specific pod
+// Get containers for a specific pod, including init and ephemeral containers
 #[tauri::command]
 pub async fn k8s_get_pod_containers(namespace: String, pod: String) -> Result<Vec<String>, String> {
     let client = get_k8s_client().map_err(|e| e.to_string())?;
@@ -953,23 +1680,7 @@ pub async fn k8s_get_pod_containers(namespace: String, pod: String) -> Result<Ve
     let pod_obj = api.get(&pod).await
         .map_err(|e| format!("Failed to get pod {}: {}", pod, e))?;
     
-    let mut containers = Vec::new();
-    
-    // Get containers from spec
-    if let Some(ref spec) = pod_obj.spec {
-        for container in &spec.containers {
-            containers.push(container.name.clone());
-        }
-        
-        // Get init containers if any
-        if let Some(ref init_containers) = spec.init_containers {
-            for container in init_containers {
-                containers.push(container.name.clone());
-            }
-        }
-    }
-    
-    Ok(containers)
+    Ok(pod_container_names(&pod_obj))
 }
 
 // Delete a pod (this will trigger a restart if managed by a deployment)
@@ -1042,3 +1753,193 @@ pub async fn k8s_health_check() -> Result<bool, String> {
 pub async fn init_k8s() -> Result<(), String> {
     init_k8s_client().await.map_err(|e| e.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelectorRequirement, ObjectMeta, OwnerReference};
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn expression(key: &str, operator: &str, values: &[&str]) -> LabelSelectorRequirement {
+        LabelSelectorRequirement {
+            key: key.to_string(),
+            operator: operator.to_string(),
+            values: Some(values.iter().map(|v| v.to_string()).collect()),
+        }
+    }
+
+    fn owner(kind: &str, name: &str) -> OwnerReference {
+        OwnerReference {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            controller: Some(true),
+            ..OwnerReference::default()
+        }
+    }
+
+    fn pod(owner: Option<OwnerReference>, pod_labels: &[(&str, &str)]) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                owner_references: owner.map(|owner| vec![owner]),
+                labels: Some(labels(pod_labels)),
+                ..ObjectMeta::default()
+            },
+            ..Pod::default()
+        }
+    }
+
+    fn replica_set(name: &str, owner: OwnerReference) -> ReplicaSet {
+        ReplicaSet {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                owner_references: Some(vec![owner]),
+                ..ObjectMeta::default()
+            },
+            ..ReplicaSet::default()
+        }
+    }
+
+    fn api_selector() -> LabelSelector {
+        LabelSelector { match_labels: Some(labels(&[("app", "api")])), ..LabelSelector::default() }
+    }
+
+    fn membership(replica_sets: Option<Vec<ReplicaSet>>) -> WorkloadMembership {
+        let names = vec!["api".to_string()];
+        WorkloadMembership {
+            replica_sets: replica_sets.map(|list| deployment_replica_sets(list, &names)),
+            names,
+            selectors: vec![api_selector()],
+        }
+    }
+
+    #[test]
+    fn match_labels_must_all_match() {
+        let selector = LabelSelector {
+            match_labels: Some(labels(&[("app", "api"), ("tier", "web")])),
+            ..LabelSelector::default()
+        };
+        assert!(selector_matches(&selector, &labels(&[("app", "api"), ("tier", "web"), ("extra", "x")])));
+        assert!(!selector_matches(&selector, &labels(&[("app", "api")])));
+        assert!(!selector_matches(&selector, &labels(&[("app", "api"), ("tier", "db")])));
+        // An empty selector matches no pods
+        assert!(!selector_matches(&LabelSelector::default(), &labels(&[("app", "api")])));
+    }
+
+    #[test]
+    fn match_expressions() {
+        let selector = |expr: LabelSelectorRequirement| LabelSelector {
+            match_expressions: Some(vec![expr]),
+            ..LabelSelector::default()
+        };
+        let api = labels(&[("app", "api")]);
+        assert!(selector_matches(&selector(expression("app", "In", &["api", "web"])), &api));
+        assert!(!selector_matches(&selector(expression("app", "In", &["web"])), &api));
+        assert!(selector_matches(&selector(expression("app", "NotIn", &["web"])), &api));
+        assert!(selector_matches(&selector(expression("tier", "NotIn", &["web"])), &api));
+        assert!(selector_matches(&selector(expression("app", "Exists", &[])), &api));
+        assert!(selector_matches(&selector(expression("tier", "DoesNotExist", &[])), &api));
+        assert!(!selector_matches(&selector(expression("app", "Bogus", &["api"])), &api));
+    }
+
+    #[test]
+    fn owner_reference_chain() {
+        let membership = membership(Some(vec![
+            replica_set("api-7d9f", owner("Deployment", "api")),
+            replica_set("worker-5c1a", owner("Deployment", "worker")),
+        ]));
+        assert!(membership.contains(&pod(Some(owner("ReplicaSet", "api-7d9f")), &[])));
+        // Another deployment's pod is excluded even when its labels match
+        assert!(!membership.contains(&pod(Some(owner("ReplicaSet", "worker-5c1a")), &[("app", "api")])));
+        assert!(membership.contains(&pod(Some(owner("StatefulSet", "api")), &[])));
+        assert!(!membership.contains(&pod(Some(owner("DaemonSet", "agent")), &[("app", "api")])));
+        // No controller, or a controller of another kind, falls back to the selector
+        assert!(membership.contains(&pod(None, &[("app", "api")])));
+        assert!(membership.contains(&pod(Some(owner("Job", "migrate")), &[("app", "api")])));
+        assert!(!membership.contains(&pod(Some(owner("Job", "migrate")), &[("app", "worker")])));
+    }
+
+    #[test]
+    fn unlisted_replica_sets_fall_back_to_selector() {
+        let membership = membership(None);
+        assert!(membership.contains(&pod(Some(owner("ReplicaSet", "api-7d9f")), &[("app", "api")])));
+        assert!(!membership.contains(&pod(Some(owner("ReplicaSet", "worker-5c1a")), &[("app", "worker")])));
+    }
+
+    fn log_at(timestamp: &str, pod: &str) -> K8sLog {
+        K8sLog {
+            timestamp: timestamp.to_string(),
+            level: "INFO".to_string(),
+            message: String::new(),
+            namespace: "default".to_string(),
+            pod: pod.to_string(),
+            container: "app".to_string(),
+            fields: HashMap::new(),
+            previous: false,
+            matched_fields: Vec::new(),
+        }
+    }
+
+    fn ten_logs() -> Vec<K8sLog> {
+        (0..10).map(|i| log_at(&format!("2024-01-01T00:00:0{}Z", i), "api-0")).collect()
+    }
+
+    #[test]
+    fn cursor_offset_is_trusted_on_the_same_snapshot() {
+        let logs = ten_logs();
+        let cursor = LogCursor::decode(&LogCursor::at(7, &logs, 4).encode()).unwrap();
+        assert_eq!(cursor.locate(7, &logs), 4);
+    }
+
+    #[test]
+    fn cursor_is_located_again_after_a_refresh() {
+        let logs = ten_logs();
+        let cursor = LogCursor::at(7, &logs, 4);
+        // A newer snapshot has two earlier lines, so the offset no longer points at the same log
+        let mut refreshed = vec![log_at("2023-12-31T23:59:58Z", "api-1"), log_at("2023-12-31T23:59:59Z", "api-1")];
+        refreshed.extend(ten_logs());
+        assert_eq!(cursor.locate(8, &refreshed), 6);
+        // The log it pointed at is gone: start at the first later one
+        refreshed.remove(6);
+        assert_eq!(cursor.locate(8, &refreshed), 6);
+        assert_eq!(refreshed[6].timestamp, "2024-01-01T00:00:05Z");
+        // Same snapshot id but a different log at the offset is not trusted either
+        let shifted: Vec<K8sLog> = refreshed[1..].to_vec();
+        assert_eq!(cursor.locate(7, &shifted), 5);
+    }
+
+    #[test]
+    fn pages_link_to_their_neighbours() {
+        let logs = ten_logs();
+        let first = log_page(1, &logs, None, Some(4));
+        assert_eq!((first.start, first.end), (0, 4));
+        assert!(first.prev_cursor.is_none());
+
+        let next = LogCursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+        let second = log_page(1, &logs, Some(&next), Some(4));
+        assert_eq!((second.start, second.end), (4, 8));
+        let prev = LogCursor::decode(second.prev_cursor.as_deref().unwrap()).unwrap();
+        assert_eq!(prev.locate(1, &logs), 0);
+
+        let last = log_page(1, &logs, LogCursor::decode(second.next_cursor.as_deref().unwrap()).ok().as_ref(), Some(4));
+        assert_eq!((last.start, last.end), (8, 10));
+        assert!(last.next_cursor.is_none());
+
+        // Without a cursor or page size everything is one page
+        let all = log_page(1, &logs, None, None);
+        assert_eq!((all.start, all.end), (0, 10));
+        assert!(all.next_cursor.is_none() && all.prev_cursor.is_none());
+    }
+
+    #[test]
+    fn malformed_cursor_is_an_invalid_query() {
+        for token in ["not base64!", "bm90IGpzb24"] {
+            match LogCursor::decode(token) {
+                Err(LogQueryError::InvalidQuery(error)) => assert_eq!(error.message, "Invalid log cursor"),
+                other => panic!("unexpected result for {token}: {other:?}"),
+            }
+        }
+    }
+}
@@ -1,4 +1,15 @@
+mod aggregate;
+mod capture;
+mod export;
 mod k8s;
+mod log_import;
+mod log_parser;
+mod log_store;
+mod multiline;
+mod patterns;
+mod saved_searches;
+mod search;
+mod trace;
 
 // Kubernetes API commands
 
@@ -17,10 +28,31 @@ pub fn run() {
             k8s::k8s_get_secrets,
             k8s::k8s_get_logs,
             k8s::k8s_get_namespace_logs,
+            k8s::k8s_follow_logs,
+            k8s::k8s_unfollow_logs,
+            k8s::k8s_validate_search_query,
             k8s::k8s_get_pod_containers,
             k8s::k8s_delete_pod,
             k8s::k8s_restart_pod,
             k8s::k8s_scale_deployment,
+            export::k8s_export_logs,
+            aggregate::k8s_aggregate_logs,
+            patterns::k8s_get_log_patterns,
+            trace::k8s_find_trace,
+            capture::k8s_start_capture,
+            capture::k8s_stop_capture,
+            capture::k8s_list_captures,
+            log_import::import_log_files,
+            log_store::log_store_search,
+            log_store::log_store_append,
+            log_store::log_store_stats,
+            log_store::log_store_set_retention,
+            log_store::log_store_clear,
+            saved_searches::saved_searches_list,
+            saved_searches::saved_searches_save,
+            saved_searches::saved_searches_delete,
+            saved_searches::saved_searches_export,
+            saved_searches::saved_searches_import,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
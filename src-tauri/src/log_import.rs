@@ -0,0 +1,512 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::k8s::{log_position, K8sLog, LogQueryError};
+use crate::log_parser::{format_timestamp, parse_log_line, split_kubelet_timestamp};
+use crate::log_store::log_store;
+use crate::multiline::assemble_multiline;
+use crate::search::{evaluate_query, matched_fields, parse_and_compile};
+
+// Offline log import
+//
+// Reads `kubectl logs` dumps, kubelet log directories, must-gather and
+// cluster-info dumps, and NDJSON exports, either as files, directories or
+// .tar.gz bundles. Each file's namespace, pod and container are read off its
+// path; lines go through the same parser and search as live logs.
+
+// Namespace for files whose path does not name one
+const DEFAULT_IMPORT_NAMESPACE: &str = "imported";
+// Lines returned by an import unless the caller asks for another limit
+const DEFAULT_IMPORT_LIMIT: usize = 5000;
+// Extensions read from directories and bundles; everything else, such as
+// must-gather's YAML and JSON resources, is skipped
+const LOG_EXTENSIONS: [&str; 4] = ["log", "txt", "ndjson", "jsonl"];
+
+// Where a file's lines came from, as far as its path tells
+#[derive(Debug, Clone, PartialEq)]
+struct SourceHint {
+    namespace: String,
+    pod: String,
+    container: String,
+    previous: bool,
+}
+
+// File name without `.gz` and its log extension, e.g. `0.log.gz` -> `0`
+fn file_stem(file: &str) -> &str {
+    let file = file.strip_suffix(".gz").unwrap_or(file);
+    match file.rsplit_once('.') {
+        Some((stem, ext)) if LOG_EXTENSIONS.contains(&ext) => stem,
+        _ => file,
+    }
+}
+
+fn infer_source(path: &str, default_namespace: &str) -> SourceHint {
+    let parts: Vec<&str> = path.split(['/', '\\']).filter(|p| !p.is_empty() && *p != ".").collect();
+    let file = parts.last().copied().unwrap_or_default();
+    let stem = file_stem(file);
+    let hint = |namespace: &str, pod: &str, container: &str, previous: bool| SourceHint {
+        namespace: namespace.to_string(),
+        pod: pod.to_string(),
+        container: container.to_string(),
+        previous,
+    };
+
+    // must-gather: namespaces/<ns>/pods/<pod>/<container>/<container>/logs/current.log
+    if let Some(i) = parts.iter().rposition(|p| *p == "pods") {
+        if i >= 2 && parts[i - 2] == "namespaces" && parts.len() > i + 3 {
+            return hint(parts[i - 1], parts[i + 1], parts[i + 2], stem == "previous");
+        }
+    }
+
+    // kubelet: /var/log/pods/<ns>_<pod>_<uid>/<container>/<restart>.log, rotated
+    // files keep the restart number in front, e.g. 0.log.20240101-000000.gz
+    if parts.len() >= 3 && file.starts_with(|c: char| c.is_ascii_digit()) {
+        let pod_dir: Vec<&str> = parts[parts.len() - 3].splitn(3, '_').collect();
+        if pod_dir.len() == 3 {
+            return hint(pod_dir[0], pod_dir[1], parts[parts.len() - 2], false);
+        }
+    }
+
+    // kubelet symlinks: /var/log/containers/<pod>_<ns>_<container>-<container id>.log
+    if let Some((name, id)) = stem.rsplit_once('-') {
+        let names: Vec<&str> = name.splitn(3, '_').collect();
+        if id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit()) && names.len() == 3 {
+            return hint(names[1], names[0], names[2], false);
+        }
+    }
+
+    // kubectl cluster-info dump: <ns>/<pod>/logs.txt
+    if stem == "logs" && parts.len() >= 3 {
+        return hint(parts[parts.len() - 3], parts[parts.len() - 2], "", false);
+    }
+
+    // kubectl logs dumps: <pod>.log or <pod>_<container>.log; names never contain `_`
+    match stem.split_once('_') {
+        Some((pod, container)) => hint(default_namespace, pod, container, false),
+        None => hint(default_namespace, stem, "", false),
+    }
+}
+
+fn is_log_file(path: &str) -> bool {
+    let file = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let file = file.strip_suffix(".gz").unwrap_or(file);
+    // Rotated kubelet logs: 0.log.20240101-000000
+    file.split('.').skip(1).any(|ext| LOG_EXTENSIONS.contains(&ext))
+}
+
+// Timestamp carried in a structured line when the kubelet prefix is missing
+fn field_timestamp(log: &K8sLog) -> Option<String> {
+    ["time", "timestamp", "ts", "@timestamp"].iter()
+        .filter_map(|key| log.fields.get(*key))
+        .find_map(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|time| format_timestamp(&time.with_timezone(&Utc)))
+}
+
+// Turns raw file lines into K8sLogs. Container runtime formats are unwrapped
+// first: CRI lines (`<time> stdout F <msg>`) and Docker's json-file lines,
+// both of which split long lines into partial records that are joined here.
+struct LineDecoder {
+    hint: SourceHint,
+    partial: Option<(String, String)>,
+    last_timestamp: Option<String>,
+}
+
+impl LineDecoder {
+    fn new(hint: SourceHint) -> Self {
+        LineDecoder { hint, partial: None, last_timestamp: None }
+    }
+
+    fn push(&mut self, line: &str, out: &mut Vec<K8sLog>) {
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() {
+            return;
+        }
+
+        if let Some((timestamp, rest)) = split_kubelet_timestamp(line) {
+            let cri = rest.strip_prefix("stdout ").or_else(|| rest.strip_prefix("stderr "));
+            if let Some(cri) = cri {
+                match cri.split_once(' ').unwrap_or((cri, "")) {
+                    ("P", text) => self.append_partial(&format_timestamp(&timestamp), text),
+                    (_, text) => {
+                        let (timestamp, text) = self.finish_partial(&format_timestamp(&timestamp), text);
+                        self.emit(&format!("{} {}", timestamp, text), out);
+                    }
+                }
+                return;
+            }
+        }
+
+        if line.starts_with('{') {
+            if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(line) {
+                // Docker json-file: {"log":"...\n","stream":"stdout","time":"..."}
+                if let (Some(Value::String(text)), Some(Value::String(time))) = (object.get("log"), object.get("time")) {
+                    match text.strip_suffix('\n') {
+                        Some(text) => {
+                            let (timestamp, text) = self.finish_partial(time, text);
+                            self.emit(&format!("{} {}", timestamp, text), out);
+                        }
+                        None => self.append_partial(time, text),
+                    }
+                    return;
+                }
+                // Lines exported from this app keep their source and level
+                if object.contains_key("timestamp") && object.contains_key("pod") && object.contains_key("message") {
+                    if let Ok(log) = serde_json::from_value::<K8sLog>(Value::Object(object)) {
+                        self.last_timestamp = Some(log.timestamp.clone());
+                        out.push(log);
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.emit(line, out);
+    }
+
+    fn append_partial(&mut self, timestamp: &str, text: &str) {
+        match self.partial {
+            Some((_, ref mut pending)) => pending.push_str(text),
+            None => self.partial = Some((timestamp.to_string(), text.to_string())),
+        }
+    }
+
+    // The full line's text and the timestamp of its first part
+    fn finish_partial(&mut self, timestamp: &str, text: &str) -> (String, String) {
+        match self.partial.take() {
+            Some((first_timestamp, mut pending)) => {
+                pending.push_str(text);
+                (first_timestamp, pending)
+            }
+            None => (timestamp.to_string(), text.to_string()),
+        }
+    }
+
+    fn emit(&mut self, line: &str, out: &mut Vec<K8sLog>) {
+        let hint = &self.hint;
+        let mut log = parse_log_line(line, &hint.namespace, &hint.pod, &hint.container);
+        log.previous = hint.previous;
+        // Lines without the kubelet prefix use their own time field, or the
+        // previous line's time, rather than the time of the import
+        if split_kubelet_timestamp(line).is_none() {
+            if let Some(timestamp) = field_timestamp(&log).or_else(|| self.last_timestamp.clone()) {
+                log.timestamp = timestamp;
+            }
+        }
+        self.last_timestamp = Some(log.timestamp.clone());
+        out.push(log);
+    }
+
+    fn finish(mut self, out: &mut Vec<K8sLog>) {
+        if let Some((timestamp, text)) = self.partial.take() {
+            self.emit(&format!("{} {}", timestamp, text), out);
+        }
+    }
+}
+
+// One file read by an import, with the source inferred from its path
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportedFile {
+    pub path: String,
+    pub namespace: String,
+    pub pod: String,
+    pub container: String,
+    pub previous: bool,
+    pub lines: usize,
+    pub error: Option<String>,
+}
+
+fn import_reader<R: Read>(reader: R, path: &str, default_namespace: &str, out: &mut Vec<K8sLog>) -> ImportedFile {
+    let hint = infer_source(path, default_namespace);
+    let mut file = ImportedFile {
+        path: path.to_string(),
+        namespace: hint.namespace.clone(),
+        pod: hint.pod.clone(),
+        container: hint.container.clone(),
+        previous: hint.previous,
+        lines: 0,
+        error: None,
+    };
+
+    let mut reader: Box<dyn BufRead> = if path.ends_with(".gz") {
+        Box::new(BufReader::new(GzDecoder::new(reader)))
+    } else {
+        Box::new(BufReader::new(reader))
+    };
+    let before = out.len();
+    let mut decoder = LineDecoder::new(hint);
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => break,
+            // Tolerate the odd non-UTF-8 byte rather than dropping the file
+            Ok(_) => decoder.push(&String::from_utf8_lossy(&buffer), out),
+            Err(e) => {
+                file.error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+    decoder.finish(out);
+    let events = assemble_multiline(out.split_off(before));
+    out.extend(events);
+    file.lines = out.len() - before;
+    file
+}
+
+fn import_archive(path: &Path, default_namespace: &str, out: &mut Vec<K8sLog>, files: &mut Vec<ImportedFile>) -> Result<(), String> {
+    let name = path.to_string_lossy();
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", name, e))?;
+    let reader: Box<dyn Read> = if name.ends_with(".tar") {
+        Box::new(BufReader::new(file))
+    } else {
+        Box::new(GzDecoder::new(BufReader::new(file)))
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    let entries = archive.entries().map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let entry_path = match entry.path() {
+            Ok(entry_path) => entry_path.to_string_lossy().into_owned(),
+            Err(_) => continue,
+        };
+        if is_log_file(&entry_path) {
+            files.push(import_reader(entry, &entry_path, default_namespace, out));
+        }
+    }
+    Ok(())
+}
+
+fn import_path(path: &Path, default_namespace: &str, out: &mut Vec<K8sLog>, files: &mut Vec<ImportedFile>) -> Result<(), String> {
+    let name = path.to_string_lossy().into_owned();
+    if path.is_dir() {
+        let mut children: Vec<_> = fs::read_dir(path)
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        children.sort();
+        for child in children {
+            let child_name = child.to_string_lossy();
+            // Linked directories can loop back up the tree; linked files, such
+            // as kubelet's /var/log/containers, are followed
+            let linked = fs::symlink_metadata(&child).is_ok_and(|m| m.file_type().is_symlink());
+            if child.is_dir() {
+                if !linked {
+                    import_path(&child, default_namespace, out, files)?;
+                }
+            } else if is_log_file(&child_name) || is_archive(&child_name) {
+                import_path(&child, default_namespace, out, files)?;
+            }
+        }
+        return Ok(());
+    }
+
+    if is_archive(&name) {
+        return import_archive(path, default_namespace, out, files);
+    }
+
+    // A single file is imported whatever its extension
+    match File::open(path) {
+        Ok(file) => files.push(import_reader(file, &name, default_namespace, out)),
+        Err(e) => return Err(format!("Failed to open {}: {}", name, e)),
+    }
+    Ok(())
+}
+
+fn is_archive(name: &str) -> bool {
+    name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".tar")
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportResponse {
+    // The newest `limit` lines matching the search, oldest first
+    pub logs: Vec<K8sLog>,
+    // Lines matching the search
+    pub total: usize,
+    // Events read from all files, after joining multiline stack traces
+    pub imported: usize,
+    // Lines written to the local log store
+    pub stored: usize,
+    pub files: Vec<ImportedFile>,
+}
+
+// Import log files, directories or bundles and search them like live logs.
+// With `store` set the lines are also kept in the local log store.
+#[tauri::command]
+pub async fn import_log_files(
+    app: AppHandle,
+    paths: Vec<String>,
+    namespace: Option<String>,
+    search: Option<String>,
+    store: Option<bool>,
+    limit: Option<usize>
+) -> Result<ImportResponse, LogQueryError> {
+    let compiled_search = match search {
+        Some(ref search_query) if !search_query.trim().is_empty() => {
+            Some(parse_and_compile(search_query).map_err(LogQueryError::InvalidQuery)?)
+        }
+        _ => None,
+    };
+    let default_namespace = namespace
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_IMPORT_NAMESPACE.to_string());
+
+    // Decoding and the store write both touch the disk, so neither runs on the async runtime
+    let (mut logs, files, stored) = tokio::task::spawn_blocking(move || {
+        let mut logs = Vec::new();
+        let mut files = Vec::new();
+        for path in paths {
+            import_path(Path::new(&path), &default_namespace, &mut logs, &mut files)?;
+        }
+        logs.sort_by(|a, b| log_position(a).cmp(&log_position(b)));
+
+        let stored = if store.unwrap_or(false) && !logs.is_empty() {
+            let store = log_store(&app)?;
+            let mut store = store.lock().map_err(|_| "Log store lock poisoned".to_string())?;
+            store.append(&logs)?
+        } else {
+            0
+        };
+        Ok::<_, String>((logs, files, stored))
+    })
+    .await
+    .map_err(|e| format!("Import failed: {}", e))??;
+    let imported = logs.len();
+
+    if let Some(ref query) = compiled_search {
+        logs.retain(|log| evaluate_query(log, query));
+        for log in logs.iter_mut() {
+            log.matched_fields = matched_fields(log, query);
+        }
+    }
+    let total = logs.len();
+    let limit = limit.filter(|l| *l > 0).unwrap_or(DEFAULT_IMPORT_LIMIT);
+    if logs.len() > limit {
+        logs.drain(..logs.len() - limit);
+    }
+
+    Ok(ImportResponse { logs, total, imported, stored, files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(namespace: &str, pod: &str, container: &str, previous: bool) -> SourceHint {
+        SourceHint {
+            namespace: namespace.to_string(),
+            pod: pod.to_string(),
+            container: container.to_string(),
+            previous,
+        }
+    }
+
+    fn decode(lines: &[&str]) -> Vec<K8sLog> {
+        let mut decoder = LineDecoder::new(source("default", "api-0", "app", false));
+        let mut out = Vec::new();
+        for line in lines {
+            decoder.push(line, &mut out);
+        }
+        decoder.finish(&mut out);
+        out
+    }
+
+    #[test]
+    fn must_gather_layout() {
+        let base = "must-gather/quay-io-image/namespaces/shop/pods/api-0";
+        assert_eq!(
+            infer_source(&format!("{}/app/app/logs/current.log", base), "imported"),
+            source("shop", "api-0", "app", false)
+        );
+        assert_eq!(
+            infer_source(&format!("{}/app/app/logs/previous.log", base), "imported"),
+            source("shop", "api-0", "app", true)
+        );
+    }
+
+    #[test]
+    fn kubelet_pod_directories() {
+        let dir = "/var/log/pods/shop_api-0_0f6b1c2e-9d1a-4c47-9a53-4c7e2b1d8f00/app";
+        assert_eq!(infer_source(&format!("{}/0.log", dir), "imported"), source("shop", "api-0", "app", false));
+        // Rotated and compressed files keep the restart number in front
+        assert_eq!(
+            infer_source(&format!("{}/1.log.20240101-000000.gz", dir), "imported"),
+            source("shop", "api-0", "app", false)
+        );
+    }
+
+    #[test]
+    fn kubelet_container_symlinks() {
+        let id = "a".repeat(64);
+        assert_eq!(
+            infer_source(&format!("/var/log/containers/api-0_shop_app-{}.log", id), "imported"),
+            source("shop", "api-0", "app", false)
+        );
+    }
+
+    #[test]
+    fn cluster_info_dump() {
+        assert_eq!(infer_source("cluster-state/shop/api-0/logs.txt", "imported"), source("shop", "api-0", "", false));
+    }
+
+    #[test]
+    fn kubectl_dump_names() {
+        assert_eq!(infer_source("dumps/api-0.log", "imported"), source("imported", "api-0", "", false));
+        assert_eq!(infer_source("dumps/api-0_sidecar.log", "imported"), source("imported", "api-0", "sidecar", false));
+        assert_eq!(infer_source("api-0.txt.gz", "staging"), source("staging", "api-0", "", false));
+    }
+
+    #[test]
+    fn docker_json_file_lines() {
+        let logs = decode(&[
+            r#"{"log":"ERROR payment declined\n","stream":"stderr","time":"2024-01-01T10:00:00.5Z"}"#,
+            r#"{"log":"a long line split ","stream":"stdout","time":"2024-01-01T10:00:01Z"}"#,
+            r#"{"log":"by the runtime\n","stream":"stdout","time":"2024-01-01T10:00:02Z"}"#,
+        ]);
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].message, "ERROR payment declined");
+        assert_eq!(logs[0].level, "ERROR");
+        assert!(logs[0].timestamp.starts_with("2024-01-01T10:00:00.5"));
+        // A joined line keeps the time of its first part
+        assert_eq!(logs[1].message, "a long line split by the runtime");
+        assert!(logs[1].timestamp.starts_with("2024-01-01T10:00:01"));
+    }
+
+    #[test]
+    fn cri_partial_lines_are_joined() {
+        let logs = decode(&[
+            "2024-01-01T10:00:00.000000001Z stdout P first part, ",
+            "2024-01-01T10:00:00.000000002Z stdout P second part, ",
+            "2024-01-01T10:00:00.000000003Z stdout F last part",
+            "2024-01-01T10:00:01Z stderr F next line",
+        ]);
+        let messages: Vec<&str> = logs.iter().map(|log| log.message.as_str()).collect();
+        assert_eq!(messages, ["first part, second part, last part", "next line"]);
+        assert_eq!(logs[0].timestamp, "2024-01-01T10:00:00.000000001Z");
+    }
+
+    #[test]
+    fn cri_partial_lines_are_joined_across_reads() {
+        // Parts of one line straddle the reader's buffer; the last part is never completed
+        let data = "2024-01-01T10:00:00Z stdout P started \n2024-01-01T10:00:00Z stdout F and finished\n\
+                    2024-01-01T10:00:01Z stdout P cut off";
+        let mut out = Vec::new();
+        let reader = BufReader::with_capacity(8, data.as_bytes());
+        let file = import_reader(reader, "/var/log/pods/shop_api-0_uid/app/0.log", "imported", &mut out);
+        assert_eq!(file.lines, 2);
+        assert_eq!(out[0].message, "started and finished");
+        assert_eq!(out[1].message, "cut off");
+        assert_eq!((out[0].namespace.as_str(), out[0].container.as_str()), ("shop", "app"));
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::k8s::K8sLog;
+
+// Split the RFC3339Nano prefix the kubelet adds when logs are requested with
+// `timestamps=true`. Returns the parsed time and the rest of the line.
+pub fn split_kubelet_timestamp(line: &str) -> Option<(DateTime<Utc>, &str)> {
+    let (prefix, rest) = match line.split_once(' ') {
+        Some(parts) => parts,
+        None => (line, ""),
+    };
+
+    let timestamp = DateTime::parse_from_rfc3339(prefix).ok()?;
+    Some((timestamp.with_timezone(&Utc), rest))
+}
+
+// Format a timestamp with fixed nanosecond precision so string order matches time order
+pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+// Normalised severities every detected level maps onto
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warning => "WARNING",
+            LogLevel::Error => "ERROR",
+            LogLevel::Fatal => "FATAL",
+        }
+    }
+
+    // Map the level names used by common logging libraries onto a severity
+    pub fn from_name(name: &str) -> Option<LogLevel> {
+        match name.trim().to_lowercase().as_str() {
+            "trace" | "trc" | "finest" | "finer" => Some(LogLevel::Trace),
+            "debug" | "dbg" | "fine" | "config" => Some(LogLevel::Debug),
+            "info" | "inf" | "information" | "informational" | "notice" => Some(LogLevel::Info),
+            "warn" | "wrn" | "warning" => Some(LogLevel::Warning),
+            "error" | "err" | "eror" | "severe" => Some(LogLevel::Error),
+            "fatal" | "ftl" | "critical" | "crit" | "panic" | "dpanic" | "alert" | "emerg" | "emergency" => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+
+    // Numeric levels as written by bunyan and pino
+    fn from_number(level: i64) -> Option<LogLevel> {
+        match level {
+            10 => Some(LogLevel::Trace),
+            20 => Some(LogLevel::Debug),
+            30 => Some(LogLevel::Info),
+            40 => Some(LogLevel::Warning),
+            50 => Some(LogLevel::Error),
+            60 => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+
+    // Syslog priority `<PRI>`: the severity is the low three bits
+    fn from_syslog_priority(priority: u32) -> LogLevel {
+        match priority % 8 {
+            0..=2 => LogLevel::Fatal,
+            3 => LogLevel::Error,
+            4 => LogLevel::Warning,
+            5 | 6 => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+}
+
+// Structured keys that commonly carry the level, in order of preference.
+// ECS nests it as {"log": {"level": ...}}, which flattens to `log.level`.
+const JSON_LEVEL_KEYS: &[&str] = &["level", "severity", "lvl", "loglevel", "log_level", "levelname", "log.level"];
+
+fn logfmt_level_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?:^|\s)(?:level|lvl|severity)=(?:"([^"]*)"|(\S+))"#).unwrap())
+}
+
+fn klog_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^([IWEF])\d{4} \d{2}:\d{2}:\d{2}").unwrap())
+}
+
+fn syslog_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^<(\d{1,3})>").unwrap())
+}
+
+fn bracketed_level_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)\[\s*(trace|debug|info|notice|warn|warning|error|err|fatal|critical|crit|severe|panic)\s*\]").unwrap())
+}
+
+// Logback/log4j (`12:00:00.123 [main] ERROR c.f.Bar - ...`), Python logging
+// (`ERROR:root:...`, `... - app - ERROR - ...`) and java.util.logging (`SEVERE: ...`)
+// all put an upper-case level token near the start of the line
+fn prefix_level_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b(TRACE|DEBUG|INFO|NOTICE|WARN|WARNING|ERROR|FATAL|CRITICAL|SEVERE|PANIC)\b").unwrap())
+}
+
+// Lower-case level at the very start, e.g. `error: connection refused`
+fn leading_level_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)^(trace|debug|info|warn|warning|error|fatal|panic)[:\s]").unwrap())
+}
+
+// How far into a line a bare level token is still considered a prefix
+const LEVEL_PREFIX_WINDOW: usize = 100;
+
+// Flatten a JSON object into dotted keys, e.g. {"http": {"status": 500}} -> "http.status"
+fn flatten_json(prefix: &str, object: &Map<String, Value>, fields: &mut HashMap<String, String>) {
+    for (key, value) in object {
+        let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            Value::Object(inner) => flatten_json(&key, inner, fields),
+            Value::String(text) => {
+                fields.insert(key, text.clone());
+            }
+            Value::Null => {
+                fields.insert(key, String::new());
+            }
+            other => {
+                fields.insert(key, other.to_string());
+            }
+        }
+    }
+}
+
+fn parse_json_fields(message: &str) -> Option<HashMap<String, String>> {
+    let trimmed = message.trim();
+    if !trimmed.starts_with('{') {
+        return None;
+    }
+
+    let value: Value = serde_json::from_str(trimmed).ok()?;
+    let mut fields = HashMap::new();
+    flatten_json("", value.as_object()?, &mut fields);
+    Some(fields)
+}
+
+fn is_logfmt_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '@'))
+}
+
+// Parse `key=value key2="quoted value"` lines. Only accepted when every token
+// is a pair and there are at least two of them, so prose containing a stray
+// `=` is not mistaken for logfmt.
+fn parse_logfmt_fields(message: &str) -> Option<HashMap<String, String>> {
+    let mut fields = HashMap::new();
+    let mut chars = message.trim().chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        if !is_logfmt_key(&key) || chars.next() != Some('=') {
+            return None;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            let mut closed = false;
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some(escaped) = chars.next() {
+                            value.push(match escaped {
+                                'n' => '\n',
+                                't' => '\t',
+                                other => other,
+                            });
+                        }
+                    }
+                    '"' => {
+                        closed = true;
+                        break;
+                    }
+                    other => value.push(other),
+                }
+            }
+            if !closed {
+                return None;
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+
+        fields.insert(key, value);
+    }
+
+    if fields.len() >= 2 {
+        Some(fields)
+    } else {
+        None
+    }
+}
+
+// Extract structured fields from a JSON or logfmt line; plain text yields none
+pub fn parse_structured_fields(message: &str) -> HashMap<String, String> {
+    parse_json_fields(message)
+        .or_else(|| parse_logfmt_fields(message))
+        .unwrap_or_default()
+}
+
+fn level_from_fields(fields: &HashMap<String, String>) -> Option<LogLevel> {
+    JSON_LEVEL_KEYS.iter()
+        .filter_map(|key| fields.get(*key))
+        .find_map(|value| {
+            LogLevel::from_name(value)
+                .or_else(|| value.parse::<i64>().ok().and_then(LogLevel::from_number))
+        })
+}
+
+// Detect the severity of a log message, defaulting to INFO when nothing matches.
+// `fields` are the structured fields already parsed from the same message.
+pub fn detect_level(message: &str, fields: &HashMap<String, String>) -> LogLevel {
+    if let Some(level) = level_from_fields(fields) {
+        return level;
+    }
+
+    if let Some(caps) = logfmt_level_regex().captures(message) {
+        let name = caps.get(1).or_else(|| caps.get(2)).map(|m| m.as_str()).unwrap_or_default();
+        if let Some(level) = LogLevel::from_name(name) {
+            return level;
+        }
+    }
+
+    if let Some(caps) = klog_regex().captures(message) {
+        return match &caps[1] {
+            "E" => LogLevel::Error,
+            "W" => LogLevel::Warning,
+            "F" => LogLevel::Fatal,
+            _ => LogLevel::Info,
+        };
+    }
+
+    if let Some(caps) = syslog_regex().captures(message) {
+        if let Ok(priority) = caps[1].parse::<u32>() {
+            return LogLevel::from_syslog_priority(priority);
+        }
+    }
+
+    let mut window_end = message.len().min(LEVEL_PREFIX_WINDOW);
+    while !message.is_char_boundary(window_end) {
+        window_end -= 1;
+    }
+    let prefix = &message[..window_end];
+
+    let token = bracketed_level_regex().captures(prefix)
+        .or_else(|| prefix_level_regex().captures(prefix))
+        .or_else(|| leading_level_regex().captures(prefix));
+    if let Some(level) = token.and_then(|caps| LogLevel::from_name(&caps[1])) {
+        return level;
+    }
+
+    LogLevel::Info
+}
+
+// Build a log entry from a raw line, using the kubelet timestamp when present
+pub fn parse_log_line(line: &str, namespace: &str, pod: &str, container: &str) -> K8sLog {
+    let (timestamp, message) = match split_kubelet_timestamp(line) {
+        Some((timestamp, message)) => (timestamp, message),
+        None => (Utc::now(), line),
+    };
+
+    let fields = parse_structured_fields(message);
+
+    K8sLog {
+        timestamp: format_timestamp(&timestamp),
+        level: detect_level(message, &fields).as_str().to_string(),
+        message: message.to_string(),
+        namespace: namespace.to_string(),
+        pod: pod.to_string(),
+        container: container.to_string(),
+        fields,
+        previous: false,
+        matched_fields: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_kubelet_timestamp() {
+        let (timestamp, rest) = split_kubelet_timestamp("2024-01-02T03:04:05.123456789Z hello world").unwrap();
+        assert_eq!(format_timestamp(&timestamp), "2024-01-02T03:04:05.123456789Z");
+        assert_eq!(rest, "hello world");
+    }
+
+    #[test]
+    fn kubelet_timestamp_is_normalised_to_utc() {
+        let (timestamp, rest) = split_kubelet_timestamp("2024-01-02T05:04:05+02:00").unwrap();
+        assert_eq!(format_timestamp(&timestamp), "2024-01-02T03:04:05.000000000Z");
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn line_without_timestamp_is_not_split() {
+        assert!(split_kubelet_timestamp("hello world").is_none());
+        assert!(split_kubelet_timestamp("").is_none());
+    }
+
+    fn level(message: &str) -> LogLevel {
+        detect_level(message, &parse_structured_fields(message))
+    }
+
+    #[test]
+    fn detects_level_from_structured_fields() {
+        assert_eq!(level(r#"{"level":"warn","msg":"slow"}"#), LogLevel::Warning);
+        assert_eq!(level(r#"{"log":{"level":"error"},"msg":"x"}"#), LogLevel::Error);
+        // bunyan/pino numeric levels
+        assert_eq!(level(r#"{"level":50,"msg":"x"}"#), LogLevel::Error);
+        assert_eq!(level("level=debug msg=starting"), LogLevel::Debug);
+    }
+
+    #[test]
+    fn detects_level_from_text_formats() {
+        assert_eq!(level("E0102 03:04:05.000000       1 controller.go:42] sync failed"), LogLevel::Error);
+        assert_eq!(level("W0102 03:04:05.000000       1 main.go:1] retrying"), LogLevel::Warning);
+        assert_eq!(level("<11>Jan  2 03:04:05 host app: failed"), LogLevel::Error);
+        assert_eq!(level("12:00:00.123 [main] ERROR c.f.Bar - boom"), LogLevel::Error);
+        assert_eq!(level("[ warn ] disk almost full"), LogLevel::Warning);
+        assert_eq!(level("SEVERE: out of memory"), LogLevel::Error);
+        assert_eq!(level("fatal: cannot start"), LogLevel::Fatal);
+    }
+
+    #[test]
+    fn level_defaults_to_info() {
+        assert_eq!(level("request completed"), LogLevel::Info);
+        // A level word far into the line is prose, not a prefix
+        let late = format!("{} ERROR", "x".repeat(LEVEL_PREFIX_WINDOW));
+        assert_eq!(level(&late), LogLevel::Info);
+        // Lower-case words are only levels at the start of the line
+        assert_eq!(level("no error here"), LogLevel::Info);
+    }
+
+    #[test]
+    fn flattens_json_fields() {
+        let fields = parse_structured_fields(r#"{"msg":"done","http":{"status":500,"path":"/api"},"user":null,"ok":true}"#);
+        assert_eq!(fields["msg"], "done");
+        assert_eq!(fields["http.status"], "500");
+        assert_eq!(fields["http.path"], "/api");
+        assert_eq!(fields["user"], "");
+        assert_eq!(fields["ok"], "true");
+    }
+
+    #[test]
+    fn parses_logfmt_fields() {
+        let fields = parse_structured_fields(r#"level=info msg="request done" duration=1.5s path=/api\ok"#);
+        assert_eq!(fields["msg"], "request done");
+        assert_eq!(fields["duration"], "1.5s");
+        assert_eq!(fields.len(), 4);
+
+        let escaped = parse_structured_fields(r#"a=1 b="say \"hi\"\n""#);
+        assert_eq!(escaped["b"], "say \"hi\"\n");
+    }
+
+    #[test]
+    fn plain_text_has_no_fields() {
+        assert!(parse_structured_fields("connection refused").is_empty());
+        // Prose with one stray `=` is not logfmt
+        assert!(parse_structured_fields("set x=1 and retry").is_empty());
+        // A single pair is not enough
+        assert!(parse_structured_fields("retries=3").is_empty());
+        assert!(parse_structured_fields(r#"a=1 b="unterminated"#).is_empty());
+        assert!(parse_structured_fields("{not json").is_empty());
+    }
+}
@@ -0,0 +1,890 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::k8s::{log_position, K8sLog, LogQueryError};
+use crate::log_parser::format_timestamp;
+use crate::search::{evaluate_query, index_tokens, parse_and_compile, parse_time, term_filter, TermFilter};
+
+// Local log store
+//
+//   <app data>/log-store/
+//     retention.json            retention policy
+//     2026-10-17T13/            one partition per hour of log time
+//       index.json              the hour's sources and inverted index
+//       000000.ndjson           append-only segment, one per source
+//
+// Lines are stored as K8sLog JSON. The index maps every lowercase token of a
+// line (see `index_tokens`) to the segment and byte offset it was written at.
+// A token found on more than MAX_TERM_POSTINGS lines of a partition loses its
+// postings and is searched by scanning the partition instead, which keeps the
+// index small next to the segments however common the token is.
+
+const STORE_DIR: &str = "log-store";
+const INDEX_FILE: &str = "index.json";
+const RETENTION_FILE: &str = "retention.json";
+const PARTITION_FORMAT: &str = "%Y-%m-%dT%H";
+
+// Minimum time between index writes for a partition that keeps growing
+const INDEX_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+// Minimum time between retention checks on append; each one walks the store
+const RETENTION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Lines a token is indexed for in one partition before it is only scanned for
+const MAX_TERM_POSTINGS: usize = 2000;
+// Longest substring the term gram index is keyed by
+const TERM_GRAM_LEN: usize = 3;
+// Lines returned by a store search unless the caller asks for another limit
+const DEFAULT_STORED_LOG_LIMIT: usize = 5000;
+
+// Where a stored line came from; one segment file per source and partition
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct LogSource {
+    pub namespace: String,
+    pub pod: String,
+    pub container: String,
+    pub previous: bool,
+}
+
+impl LogSource {
+    fn of(log: &K8sLog) -> Self {
+        LogSource {
+            namespace: log.namespace.clone(),
+            pod: log.pod.clone(),
+            container: log.container.clone(),
+            previous: log.previous,
+        }
+    }
+}
+
+// How much history to keep. Whole hourly partitions are dropped, oldest first;
+// a partition's age is from the later of its hour and its last write.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetentionPolicy {
+    pub max_bytes: u64,
+    pub max_age_hours: u64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            max_bytes: 1024 * 1024 * 1024,
+            max_age_hours: 24 * 7,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct PartitionIndex {
+    // Segment id is the position in this list
+    segments: Vec<LogSource>,
+    // Token -> (segment, byte offset) of every line containing it
+    terms: BTreeMap<String, Vec<(u32, u64)>>,
+    // Tokens past MAX_TERM_POSTINGS; kept in `terms` with no postings
+    #[serde(default)]
+    saturated: BTreeSet<String>,
+    lines: u64,
+    // Bytes of each segment covered by `terms`; anything past this is
+    // indexed again when the partition is loaded
+    #[serde(default)]
+    indexed_bytes: Vec<u64>,
+}
+
+// Substrings of up to TERM_GRAM_LEN characters of every indexed term, so a
+// needle token finds the terms containing it without scanning the dictionary.
+// Built from the terms when a partition is loaded rather than saved.
+#[derive(Default)]
+struct TermGrams {
+    terms: Vec<String>,
+    // Gram -> ids of the terms containing it, ascending
+    grams: HashMap<String, Vec<u32>>,
+}
+
+impl TermGrams {
+    fn insert(&mut self, term: &str) {
+        let id = self.terms.len() as u32;
+        self.terms.push(term.to_string());
+        let chars: Vec<char> = term.chars().collect();
+        for len in 1..=TERM_GRAM_LEN.min(chars.len()) {
+            for window in chars.windows(len) {
+                let ids = self.grams.entry(window.iter().collect()).or_default();
+                // A gram repeated within the term is listed once
+                if ids.last() != Some(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+    }
+
+    // Indexed terms containing `needle`, checked against the rarest of its grams
+    fn containing<'a>(&'a self, needle: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let chars: Vec<char> = needle.chars().collect();
+        let len = TERM_GRAM_LEN.min(chars.len());
+        let rarest = chars.windows(len.max(1))
+            .map(|window| self.grams.get(&window.iter().collect::<String>()).map_or(&[][..], Vec::as_slice))
+            .min_by_key(|ids| ids.len())
+            .unwrap_or_default();
+        rarest.iter()
+            .map(|id| self.terms[*id as usize].as_str())
+            .filter(move |term| term.contains(needle))
+    }
+}
+
+struct Partition {
+    dir: PathBuf,
+    index: PartitionIndex,
+    grams: TermGrams,
+    dirty: bool,
+    // A new source was added; saved right away so its segment is never orphaned
+    sources_changed: bool,
+    last_saved: Instant,
+}
+
+impl Partition {
+    fn load(dir: PathBuf) -> Result<Self, String> {
+        let index_path = dir.join(INDEX_FILE);
+        let index = if index_path.exists() {
+            let data = fs::read(&index_path)
+                .map_err(|e| format!("Failed to read {}: {}", index_path.display(), e))?;
+            serde_json::from_slice(&data)
+                .map_err(|e| format!("Failed to parse {}: {}", index_path.display(), e))?
+        } else {
+            PartitionIndex::default()
+        };
+        let mut grams = TermGrams::default();
+        for term in index.terms.keys() {
+            grams.insert(term);
+        }
+        let mut partition = Partition {
+            dir,
+            index,
+            grams,
+            dirty: false,
+            sources_changed: false,
+            last_saved: Instant::now(),
+        };
+        partition.catch_up()?;
+        Ok(partition)
+    }
+
+    // Index lines written after the last saved index, e.g. before a crash
+    fn catch_up(&mut self) -> Result<(), String> {
+        self.index.indexed_bytes.resize(self.index.segments.len(), 0);
+        for segment in 0..self.index.segments.len() as u32 {
+            let path = self.segment_path(segment);
+            let Ok(file) = File::open(&path) else { continue };
+            let mut offset = self.index.indexed_bytes[segment as usize];
+            if file.metadata().map(|m| m.len()).unwrap_or(0) <= offset {
+                continue;
+            }
+
+            let mut reader = BufReader::new(file);
+            reader.seek(SeekFrom::Start(offset))
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let read = reader.read_line(&mut line)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                // Stop at the end or at a torn last line
+                if read == 0 || !line.ends_with('\n') {
+                    break;
+                }
+                if let Ok(log) = serde_json::from_str::<K8sLog>(&line) {
+                    self.index_line(segment, offset, &log);
+                }
+                offset += read as u64;
+            }
+            self.index.indexed_bytes[segment as usize] = offset;
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    fn index_line(&mut self, segment: u32, offset: u64, log: &K8sLog) {
+        let tokens: BTreeSet<String> = indexed_values(log).flat_map(index_tokens).collect();
+        for token in tokens {
+            if self.index.saturated.contains(&token) {
+                continue;
+            }
+            if !self.index.terms.contains_key(&token) {
+                self.grams.insert(&token);
+            }
+            let postings = self.index.terms.entry(token.clone()).or_default();
+            postings.push((segment, offset));
+            if postings.len() > MAX_TERM_POSTINGS {
+                *postings = Vec::new();
+                self.index.saturated.insert(token);
+            }
+        }
+        self.index.lines += 1;
+    }
+
+    fn segment_path(&self, segment: u32) -> PathBuf {
+        self.dir.join(format!("{:06}.ndjson", segment))
+    }
+
+    fn segment_for(&mut self, source: &LogSource) -> u32 {
+        match self.index.segments.iter().position(|s| s == source) {
+            Some(position) => position as u32,
+            None => {
+                self.index.segments.push(source.clone());
+                self.index.indexed_bytes.push(0);
+                self.sources_changed = true;
+                (self.index.segments.len() - 1) as u32
+            }
+        }
+    }
+
+    fn append(&mut self, source: &LogSource, logs: &[&K8sLog]) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create {}: {}", self.dir.display(), e))?;
+        let segment = self.segment_for(source);
+        let path = self.segment_path(segment);
+        let file = OpenOptions::new().create(true).append(true).open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut offset = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let mut writer = BufWriter::new(file);
+
+        for log in logs {
+            let mut line = serde_json::to_vec(log).map_err(|e| e.to_string())?;
+            line.push(b'\n');
+            writer.write_all(&line)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+            self.index_line(segment, offset, log);
+            offset += line.len() as u64;
+        }
+
+        writer.flush().map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        self.index.indexed_bytes[segment as usize] = offset;
+        self.dirty = true;
+        Ok(())
+    }
+
+    // Write the index next to the segments; the rename keeps a crash from
+    // leaving a half-written index behind
+    fn save(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let data = serde_json::to_vec(&self.index).map_err(|e| e.to_string())?;
+        let temp_path = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&temp_path, data)
+            .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+        fs::rename(&temp_path, self.dir.join(INDEX_FILE))
+            .map_err(|e| format!("Failed to save index in {}: {}", self.dir.display(), e))?;
+        self.dirty = false;
+        self.sources_changed = false;
+        self.last_saved = Instant::now();
+        Ok(())
+    }
+
+    // Line locations that may satisfy a term filter, or None when a saturated
+    // token means the partition has to be scanned. A needle token matches
+    // every indexed token containing it, so partial words still find lines.
+    fn candidates(&self, filter: &TermFilter) -> Option<BTreeSet<(u32, u64)>> {
+        match filter {
+            TermFilter::Token(token) => {
+                let mut lines = BTreeSet::new();
+                for term in self.grams.containing(token) {
+                    if self.index.saturated.contains(term) {
+                        return None;
+                    }
+                    if let Some(postings) = self.index.terms.get(term) {
+                        lines.extend(postings.iter().copied());
+                    }
+                }
+                Some(lines)
+            }
+            // Any filter the index can answer narrows the others down
+            TermFilter::And(filters) => filters.iter()
+                .filter_map(|f| self.candidates(f))
+                .reduce(|acc, set| acc.intersection(&set).copied().collect()),
+            TermFilter::Or(filters) => filters.iter()
+                .map(|f| self.candidates(f))
+                .try_fold(BTreeSet::new(), |mut acc, set| {
+                    acc.extend(set?);
+                    Some(acc)
+                }),
+        }
+    }
+
+    fn read_lines_at(&self, segment: u32, offsets: &[u64], out: &mut Vec<K8sLog>) -> Result<(), String> {
+        let path = self.segment_path(segment);
+        let file = File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        for offset in offsets {
+            reader.seek(SeekFrom::Start(*offset))
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            line.clear();
+            reader.read_line(&mut line)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if let Ok(log) = serde_json::from_str(&line) {
+                out.push(log);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_segment(&self, segment: u32, out: &mut Vec<K8sLog>) -> Result<(), String> {
+        let path = self.segment_path(segment);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(format!("Failed to open {}: {}", path.display(), e)),
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            // A torn last line from an interrupted write is skipped
+            if let Ok(log) = serde_json::from_str(&line) {
+                out.push(log);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Values whose tokens are indexed; the same set an unfielded search looks at
+fn indexed_values(log: &K8sLog) -> impl Iterator<Item = &str> {
+    [
+        log.message.as_str(),
+        log.pod.as_str(),
+        log.container.as_str(),
+        log.level.as_str(),
+        log.namespace.as_str(),
+    ]
+    .into_iter()
+    .chain(log.fields.values().map(String::as_str))
+}
+
+fn partition_name(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+        .format(PARTITION_FORMAT)
+        .to_string()
+}
+
+fn partition_start(name: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(&format!("{}:00", name), "%Y-%m-%dT%H:%M")
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.metadata().ok())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+// A search over stored history
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct StoredLogsQuery {
+    pub namespace: Option<String>,
+    pub pods: Option<Vec<String>>,
+    pub search: Option<String>,
+    // RFC3339 or relative, e.g. "-2h"
+    pub since_time: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StoredLogsResponse {
+    // The newest `limit` matches, oldest first
+    pub logs: Vec<K8sLog>,
+    pub total: usize,
+    pub partitions_scanned: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogStoreStats {
+    pub path: String,
+    pub partitions: usize,
+    pub bytes: u64,
+    pub oldest_partition: Option<String>,
+    pub newest_partition: Option<String>,
+    pub retention: RetentionPolicy,
+}
+
+pub struct LogStore {
+    root: PathBuf,
+    retention: RetentionPolicy,
+    // Partitions loaded so far, by name
+    partitions: HashMap<String, Partition>,
+    last_retention_check: Option<Instant>,
+}
+
+impl LogStore {
+    pub fn open(root: PathBuf) -> Result<Self, String> {
+        fs::create_dir_all(&root)
+            .map_err(|e| format!("Failed to create log store at {}: {}", root.display(), e))?;
+        let retention = fs::read(root.join(RETENTION_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Ok(LogStore { root, retention, partitions: HashMap::new(), last_retention_check: None })
+    }
+
+    // Partition directory names on disk, oldest first
+    fn partition_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.root)
+            .map(|entries| {
+                entries.filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| partition_start(name).is_some())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    fn partition(&mut self, name: &str) -> Result<&mut Partition, String> {
+        if !self.partitions.contains_key(name) {
+            let partition = Partition::load(self.root.join(name))?;
+            self.partitions.insert(name.to_string(), partition);
+        }
+        Ok(self.partitions.get_mut(name).unwrap())
+    }
+
+    // Append logs to their source's segment in the partition for their hour
+    pub fn append(&mut self, logs: &[K8sLog]) -> Result<usize, String> {
+        let mut groups: BTreeMap<String, HashMap<LogSource, Vec<&K8sLog>>> = BTreeMap::new();
+        for log in logs {
+            groups.entry(partition_name(&log.timestamp))
+                .or_default()
+                .entry(LogSource::of(log))
+                .or_default()
+                .push(log);
+        }
+
+        for (name, sources) in groups {
+            let partition = self.partition(&name)?;
+            for (source, source_logs) in sources {
+                partition.append(&source, &source_logs)?;
+            }
+            // Captures append every few seconds; rewriting the index each time
+            // would cost more than the lines themselves
+            if partition.sources_changed || partition.last_saved.elapsed() >= INDEX_SAVE_INTERVAL {
+                partition.save()?;
+            }
+        }
+
+        if self.last_retention_check.is_none_or(|checked| checked.elapsed() >= RETENTION_CHECK_INTERVAL) {
+            self.enforce_retention()?;
+        }
+        Ok(logs.len())
+    }
+
+    pub fn search(&mut self, query: &StoredLogsQuery) -> Result<StoredLogsResponse, LogQueryError> {
+        let compiled_search = match query.search {
+            Some(ref search) if !search.trim().is_empty() => {
+                Some(parse_and_compile(search).map_err(LogQueryError::InvalidQuery)?)
+            }
+            _ => None,
+        };
+        let filter = compiled_search.as_ref().and_then(term_filter);
+
+        let now = Utc::now();
+        let bound = |value: &Option<String>, name: &str| -> Result<Option<DateTime<Utc>>, String> {
+            match value {
+                Some(text) => parse_time(text, now)
+                    .map(Some)
+                    .ok_or_else(|| format!("Invalid {} \"{}\"", name, text)),
+                None => Ok(None),
+            }
+        };
+        let from = bound(&query.since_time, "since_time")?;
+        let to = bound(&query.until, "until")?;
+        let from_text = from.as_ref().map(format_timestamp);
+        let to_text = to.as_ref().map(format_timestamp);
+
+        let wanted_source = |source: &LogSource| {
+            query.namespace.as_ref().is_none_or(|namespace| &source.namespace == namespace)
+                && query.pods.as_ref().is_none_or(|pods| pods.is_empty() || pods.contains(&source.pod))
+        };
+
+        let mut logs = Vec::new();
+        let mut partitions_scanned = 0;
+        for name in self.partition_names() {
+            let Some(start) = partition_start(&name) else { continue };
+            if from.is_some_and(|from| start + chrono::Duration::hours(1) <= from)
+                || to.is_some_and(|to| start > to)
+            {
+                continue;
+            }
+            partitions_scanned += 1;
+
+            let partition = self.partition(&name)?;
+            let segments: Vec<u32> = partition.index.segments.iter()
+                .enumerate()
+                .filter(|(_, source)| wanted_source(source))
+                .map(|(segment, _)| segment as u32)
+                .collect();
+
+            let mut partition_logs = Vec::new();
+            match filter.as_ref().and_then(|filter| partition.candidates(filter)) {
+                Some(candidates) => {
+                    let mut offsets: BTreeMap<u32, Vec<u64>> = BTreeMap::new();
+                    for (segment, offset) in candidates {
+                        if segments.contains(&segment) {
+                            offsets.entry(segment).or_default().push(offset);
+                        }
+                    }
+                    for (segment, segment_offsets) in offsets {
+                        partition.read_lines_at(segment, &segment_offsets, &mut partition_logs)?;
+                    }
+                }
+                None => {
+                    for segment in segments {
+                        partition.read_segment(segment, &mut partition_logs)?;
+                    }
+                }
+            }
+
+            partition_logs.retain(|log| {
+                from_text.as_ref().is_none_or(|from| &log.timestamp >= from)
+                    && to_text.as_ref().is_none_or(|to| &log.timestamp <= to)
+                    && compiled_search.as_ref().is_none_or(|search| evaluate_query(log, search))
+            });
+            logs.append(&mut partition_logs);
+        }
+
+        logs.sort_by(|a, b| log_position(a).cmp(&log_position(b)));
+        let total = logs.len();
+        let limit = query.limit.filter(|l| *l > 0).unwrap_or(DEFAULT_STORED_LOG_LIMIT);
+        if logs.len() > limit {
+            logs.drain(..logs.len() - limit);
+        }
+
+        Ok(StoredLogsResponse { logs, total, partitions_scanned })
+    }
+
+    // Drop partitions past the age limit, then the oldest until under the size limit
+    pub fn enforce_retention(&mut self) -> Result<(), String> {
+        self.last_retention_check = Some(Instant::now());
+        let cutoff = Utc::now() - chrono::Duration::hours(self.retention.max_age_hours as i64);
+        let mut partitions: Vec<(String, u64)> = Vec::new();
+        for name in self.partition_names() {
+            // Age counts from the last write too, so imported old logs are not dropped on arrival
+            let dir = self.root.join(&name);
+            let written = fs::metadata(&dir).and_then(|m| m.modified()).map(DateTime::<Utc>::from).ok();
+            let expired = partition_start(&name).is_some_and(|start| start + chrono::Duration::hours(1) < cutoff)
+                && written.is_none_or(|written| written < cutoff);
+            if expired {
+                self.remove_partition(&name)?;
+            } else {
+                let size = dir_size(&dir);
+                partitions.push((name, size));
+            }
+        }
+
+        let mut total: u64 = partitions.iter().map(|(_, size)| size).sum();
+        // The newest partition is kept even when it alone is over the limit
+        for (name, size) in partitions.iter().take(partitions.len().saturating_sub(1)) {
+            if total <= self.retention.max_bytes {
+                break;
+            }
+            self.remove_partition(name)?;
+            total -= size;
+        }
+        Ok(())
+    }
+
+    fn remove_partition(&mut self, name: &str) -> Result<(), String> {
+        self.partitions.remove(name);
+        let dir = self.root.join(name);
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove {}: {}", dir.display(), e))
+    }
+
+    pub fn set_retention(&mut self, retention: RetentionPolicy) -> Result<(), String> {
+        let data = serde_json::to_vec_pretty(&retention).map_err(|e| e.to_string())?;
+        fs::write(self.root.join(RETENTION_FILE), data)
+            .map_err(|e| format!("Failed to save retention policy: {}", e))?;
+        self.retention = retention;
+        self.enforce_retention()
+    }
+
+    pub fn clear(&mut self) -> Result<(), String> {
+        for name in self.partition_names() {
+            self.remove_partition(&name)?;
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> LogStoreStats {
+        let names = self.partition_names();
+        LogStoreStats {
+            path: self.root.display().to_string(),
+            partitions: names.len(),
+            bytes: names.iter().map(|name| dir_size(&self.root.join(name))).sum(),
+            oldest_partition: names.first().cloned(),
+            newest_partition: names.last().cloned(),
+            retention: self.retention.clone(),
+        }
+    }
+}
+
+static LOG_STORE: OnceLock<Mutex<LogStore>> = OnceLock::new();
+
+// The store under the app data directory, opened on first use
+pub fn log_store(app: &AppHandle) -> Result<&'static Mutex<LogStore>, String> {
+    if let Some(store) = LOG_STORE.get() {
+        return Ok(store);
+    }
+    let data_dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    let store = LogStore::open(data_dir.join(STORE_DIR))?;
+    Ok(LOG_STORE.get_or_init(|| Mutex::new(store)))
+}
+
+// Run store work off the async runtime; searches and appends read and write segment files
+async fn with_store<T, E>(app: &AppHandle, work: impl FnOnce(&mut LogStore) -> Result<T, E> + Send + 'static) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<String> + Send + 'static,
+{
+    let store = log_store(app)?;
+    tokio::task::spawn_blocking(move || {
+        let mut store = store.lock().map_err(|_| "Log store lock poisoned".to_string())?;
+        work(&mut store)
+    })
+    .await
+    .map_err(|e| format!("Log store task failed: {}", e))?
+}
+
+// Search captured logs without touching the cluster
+#[tauri::command]
+pub async fn log_store_search(app: AppHandle, query: StoredLogsQuery) -> Result<StoredLogsResponse, LogQueryError> {
+    with_store(&app, move |store| store.search(&query)).await
+}
+
+// Keep a set of already fetched logs, e.g. the current view
+#[tauri::command]
+pub async fn log_store_append(app: AppHandle, logs: Vec<K8sLog>) -> Result<usize, String> {
+    with_store(&app, move |store| store.append(&logs)).await
+}
+
+#[tauri::command]
+pub async fn log_store_stats(app: AppHandle) -> Result<LogStoreStats, String> {
+    with_store(&app, |store| Ok(store.stats())).await
+}
+
+#[tauri::command]
+pub async fn log_store_set_retention(app: AppHandle, retention: RetentionPolicy) -> Result<LogStoreStats, String> {
+    with_store(&app, move |store| {
+        store.set_retention(retention)?;
+        Ok(store.stats())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn log_store_clear(app: AppHandle) -> Result<(), String> {
+    with_store(&app, |store| store.clear()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::SystemTime;
+
+    // A store directory under the system temp dir, removed when dropped
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new() -> Self {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "log-store-test-{}-{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            TestDir(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn log(timestamp: &str, pod: &str, message: &str) -> K8sLog {
+        K8sLog {
+            timestamp: timestamp.to_string(),
+            level: "INFO".to_string(),
+            message: message.to_string(),
+            namespace: "default".to_string(),
+            pod: pod.to_string(),
+            container: "app".to_string(),
+            fields: HashMap::new(),
+            previous: false,
+            matched_fields: Vec::new(),
+        }
+    }
+
+    fn search(store: &mut LogStore, text: &str) -> Vec<String> {
+        let query = StoredLogsQuery { search: Some(text.to_string()), ..StoredLogsQuery::default() };
+        store.search(&query).unwrap().logs.into_iter().map(|log| log.message).collect()
+    }
+
+    fn filter(text: &str) -> TermFilter {
+        term_filter(&parse_and_compile(text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn appended_logs_are_found_after_reopening() {
+        let dir = TestDir::new();
+        let mut store = LogStore::open(dir.0.clone()).unwrap();
+        store.append(&[
+            log("2024-01-01T10:00:00Z", "api-0", "connection refused"),
+            log("2024-01-01T10:00:01Z", "api-1", "request served"),
+            log("2024-01-01T11:00:00Z", "api-0", "connection reset"),
+        ]).unwrap();
+        assert_eq!(search(&mut store, "connection"), ["connection refused", "connection reset"]);
+
+        let mut reopened = LogStore::open(dir.0.clone()).unwrap();
+        assert_eq!(search(&mut reopened, "connection"), ["connection refused", "connection reset"]);
+        let by_pod = StoredLogsQuery { pods: Some(vec!["api-1".to_string()]), ..StoredLogsQuery::default() };
+        assert_eq!(reopened.search(&by_pod).unwrap().logs[0].message, "request served");
+        let after = StoredLogsQuery {
+            since_time: Some("2024-01-01T10:30:00Z".to_string()),
+            ..StoredLogsQuery::default()
+        };
+        // Hour 10 overlaps the bound and is read; its line is filtered out
+        let response = reopened.search(&after).unwrap();
+        assert_eq!((response.total, response.partitions_scanned), (1, 2));
+    }
+
+    #[test]
+    fn term_grams_find_terms_by_substring() {
+        let mut grams = TermGrams::default();
+        for term in ["connection", "connected", "timeout", "a"] {
+            grams.insert(term);
+        }
+        let mut found: Vec<&str> = grams.containing("nnec").collect();
+        found.sort();
+        assert_eq!(found, ["connected", "connection"]);
+        assert_eq!(grams.containing("out").collect::<Vec<_>>(), ["timeout"]);
+        assert_eq!(grams.containing("a").collect::<Vec<_>>(), ["a"]);
+        assert_eq!(grams.containing("xyz").count(), 0);
+    }
+
+    #[test]
+    fn partial_words_use_the_index() {
+        let dir = TestDir::new();
+        let mut store = LogStore::open(dir.0.clone()).unwrap();
+        store.append(&[
+            log("2024-01-01T10:00:00Z", "api-0", "connection refused"),
+            log("2024-01-01T10:00:01Z", "api-0", "request served"),
+        ]).unwrap();
+        let partition = store.partition("2024-01-01T10").unwrap();
+        assert_eq!(partition.candidates(&filter("nnect")).unwrap().len(), 1);
+        assert_eq!(partition.candidates(&filter("nnect OR serv")).unwrap().len(), 2);
+        assert_eq!(partition.candidates(&filter("nnect AND serv")).unwrap().len(), 0);
+        assert_eq!(search(&mut store, "nnect"), ["connection refused"]);
+    }
+
+    #[test]
+    fn catch_up_indexes_complete_lines_only() {
+        let dir = TestDir::new();
+        let mut store = LogStore::open(dir.0.clone()).unwrap();
+        store.append(&[log("2024-01-01T10:00:00Z", "api-0", "first line")]).unwrap();
+        let partition_dir = dir.0.join("2024-01-01T10");
+        let segment = partition_dir.join("000000.ndjson");
+        let indexed = fs::metadata(&segment).unwrap().len();
+
+        // Written after the index was saved: one whole line, then a torn one
+        let mut whole = serde_json::to_vec(&log("2024-01-01T10:00:01Z", "api-0", "second line")).unwrap();
+        whole.push(b'\n');
+        let torn = br#"{"timestamp":"2024-01-01T10:00:02Z","mess"#;
+        let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+        file.write_all(&whole).unwrap();
+        file.write_all(torn).unwrap();
+        drop(file);
+
+        let partition = Partition::load(partition_dir).unwrap();
+        assert_eq!(partition.index.lines, 2);
+        assert_eq!(partition.index.indexed_bytes, [indexed + whole.len() as u64]);
+        assert_eq!(partition.candidates(&filter("second")).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn common_tokens_fall_back_to_a_scan() {
+        let dir = TestDir::new();
+        let mut store = LogStore::open(dir.0.clone()).unwrap();
+        let logs: Vec<K8sLog> = (0..=MAX_TERM_POSTINGS)
+            .map(|i| log("2024-01-01T10:00:00Z", "api-0", &format!("request {}", i)))
+            .collect();
+        store.append(&logs).unwrap();
+
+        let partition = store.partition("2024-01-01T10").unwrap();
+        assert!(partition.index.saturated.contains("request"));
+        assert!(partition.index.terms["request"].is_empty());
+        assert!(partition.candidates(&filter("request")).is_none());
+        // The rare token still narrows an AND down
+        assert_eq!(partition.candidates(&filter("request AND 1999")).unwrap().len(), 1);
+        partition.save().unwrap();
+
+        let mut reopened = LogStore::open(dir.0.clone()).unwrap();
+        let query = StoredLogsQuery {
+            search: Some("request".to_string()),
+            limit: Some(usize::MAX),
+            ..StoredLogsQuery::default()
+        };
+        assert_eq!(reopened.search(&query).unwrap().total, MAX_TERM_POSTINGS + 1);
+    }
+
+    #[test]
+    fn retention_drops_the_oldest_partitions_over_the_size_limit() {
+        let dir = TestDir::new();
+        let mut store = LogStore::open(dir.0.clone()).unwrap();
+        store.append(&[
+            log("2024-01-01T10:00:00Z", "api-0", "one"),
+            log("2024-01-01T11:00:00Z", "api-0", "two"),
+            log("2024-01-01T12:00:00Z", "api-0", "three"),
+        ]).unwrap();
+        let newest = dir_size(&dir.0.join("2024-01-01T12"));
+        let newer = dir_size(&dir.0.join("2024-01-01T11"));
+
+        store.set_retention(RetentionPolicy { max_bytes: newest + newer, max_age_hours: 24 * 365 * 100 }).unwrap();
+        assert_eq!(store.partition_names(), ["2024-01-01T11", "2024-01-01T12"]);
+        // The newest partition stays even when it alone is over the limit
+        store.set_retention(RetentionPolicy { max_bytes: 1, max_age_hours: 24 * 365 * 100 }).unwrap();
+        assert_eq!(store.partition_names(), ["2024-01-01T12"]);
+    }
+
+    #[test]
+    fn retention_drops_partitions_by_hour_and_last_write() {
+        let dir = TestDir::new();
+        let mut store = LogStore::open(dir.0.clone()).unwrap();
+        store.append(&[
+            log("2024-01-01T10:00:00Z", "api-0", "old and untouched"),
+            log("2024-01-01T11:00:00Z", "api-0", "old but just imported"),
+        ]).unwrap();
+        let month_ago = SystemTime::now() - Duration::from_secs(30 * 24 * 3600);
+        File::open(dir.0.join("2024-01-01T10")).unwrap().set_modified(month_ago).unwrap();
+
+        store.set_retention(RetentionPolicy { max_bytes: u64::MAX, max_age_hours: 24 }).unwrap();
+        assert_eq!(store.partition_names(), ["2024-01-01T11"]);
+    }
+}
@@ -0,0 +1,283 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::k8s::K8sLog;
+use crate::log_parser::LogLevel;
+
+// Multiline event assembly
+//
+// Stack traces arrive one line per K8sLog. Lines from a single source are
+// joined into the event they continue, with the message lines separated by
+// "\n", the first line's timestamp and fields, and the most severe level:
+//
+//   - Java and friends: lines starting with whitespace, "at ", "Caused by:"
+//     or "... 12 more"
+//   - Python: a "Traceback (most recent call last):" block through its final
+//     exception line, attached to the line before it when logged in the same second
+//   - Go: "panic:" / "fatal error:" and the goroutine dump that follows
+
+// Upper bound on lines in one event, so a misdetected stream cannot grow forever
+const MAX_EVENT_LINES: usize = 1000;
+
+fn go_frame_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // `main.main()`, `net/http.(*conn).serve(0xc000...)`, `created by main.run in goroutine 1`
+    RE.get_or_init(|| Regex::new(r"^(created by \S+.*|\S+\(.*\))$").unwrap())
+}
+
+fn go_goroutine_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^goroutine \d+ \[[^\]]*\]:$").unwrap())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Block {
+    None,
+    // Inside a Python traceback; ends after the first unindented line
+    PythonTraceback,
+    // Inside a Go panic or goroutine dump
+    GoDump,
+}
+
+fn starts_indented(message: &str) -> bool {
+    message.starts_with([' ', '\t'])
+}
+
+fn is_java_continuation(message: &str) -> bool {
+    starts_indented(message)
+        || message.starts_with("at ")
+        || message.starts_with("Caused by:")
+        || message.starts_with("Suppressed:")
+        || (message.starts_with("... ") && message.ends_with(" more"))
+}
+
+fn is_python_traceback_start(message: &str) -> bool {
+    message.starts_with("Traceback (most recent call last):")
+}
+
+fn is_python_chain(message: &str) -> bool {
+    message.starts_with("During handling of the above exception")
+        || message.starts_with("The above exception was the direct cause")
+}
+
+fn is_go_panic_start(message: &str) -> bool {
+    message.starts_with("panic: ") || message.starts_with("fatal error: ")
+}
+
+fn is_go_dump_line(message: &str) -> bool {
+    starts_indented(message)
+        || go_goroutine_regex().is_match(message)
+        || message.starts_with("[signal ")
+        || message.starts_with("exit status ")
+        || message.starts_with("panic: ")
+        || message.starts_with("[recovered]")
+        || go_frame_regex().is_match(message)
+}
+
+// Joins continuation lines of one source into events. Feed lines in order
+// with `push`; completed events come back as they close, and `finish`
+// returns the one still open.
+pub struct MultilineAssembler {
+    pending: Option<K8sLog>,
+    lines: usize,
+    block: Block,
+}
+
+impl MultilineAssembler {
+    pub fn new() -> Self {
+        MultilineAssembler { pending: None, lines: 0, block: Block::None }
+    }
+
+    pub fn push(&mut self, log: K8sLog) -> Option<K8sLog> {
+        let message = log.message.as_str();
+        let joins = self.pending.is_some() && self.lines < MAX_EVENT_LINES && match self.block {
+            Block::PythonTraceback => true,
+            Block::GoDump => is_go_dump_line(message),
+            Block::None => {
+                is_java_continuation(message)
+                    || is_python_chain(message)
+                    // A logged exception writes its message and trace together;
+                    // a trace on its own (an uncaught exception) starts a new event
+                    || ((is_python_traceback_start(message) || go_goroutine_regex().is_match(message))
+                        && self.pending.as_ref().is_some_and(|event| same_second(&event.timestamp, &log.timestamp)))
+            }
+        };
+
+        // Block state for the line just seen
+        let next_block = if is_python_traceback_start(message) {
+            Block::PythonTraceback
+        } else if is_go_panic_start(message) || go_goroutine_regex().is_match(message) {
+            Block::GoDump
+        } else if self.block == Block::PythonTraceback && joins && !starts_indented(message) && !is_python_chain(message) {
+            // The unindented exception line closes the traceback
+            Block::None
+        } else if self.block == Block::GoDump && !joins {
+            Block::None
+        } else {
+            self.block
+        };
+        let raised = match next_block {
+            Block::PythonTraceback => Some(LogLevel::Error),
+            Block::GoDump => Some(LogLevel::Fatal),
+            Block::None => None,
+        };
+        self.block = next_block;
+
+        let completed = if joins {
+            if let Some(ref mut event) = self.pending {
+                event.message.push('\n');
+                event.message.push_str(&log.message);
+                raise_level(event, LogLevel::from_name(&log.level));
+            }
+            self.lines += 1;
+            None
+        } else {
+            self.lines = 1;
+            self.pending.replace(log)
+        };
+
+        if let (Some(level), Some(ref mut event)) = (raised, self.pending.as_mut()) {
+            raise_level(event, Some(level));
+        }
+        completed
+    }
+
+    // An event has been started and may still grow
+    pub fn is_open(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn finish(&mut self) -> Option<K8sLog> {
+        self.block = Block::None;
+        self.lines = 0;
+        self.pending.take()
+    }
+}
+
+impl Default for MultilineAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn same_second(a: &str, b: &str) -> bool {
+    // Normalised RFC3339: "2024-01-01T00:00:00" is the first 19 characters
+    // `get` rather than slicing: a malformed timestamp may not split at a char boundary
+    a.get(..19).is_some_and(|second| b.get(..19) == Some(second))
+}
+
+fn raise_level(event: &mut K8sLog, level: Option<LogLevel>) {
+    let Some(level) = level else { return };
+    let current = LogLevel::from_name(&event.level);
+    if current.is_none_or(|current| level > current) {
+        event.level = level.as_str().to_string();
+    }
+}
+
+// Assemble a whole batch of lines that all come from one source
+pub fn assemble_multiline(logs: Vec<K8sLog>) -> Vec<K8sLog> {
+    let mut assembler = MultilineAssembler::new();
+    let mut events = Vec::with_capacity(logs.len());
+    for log in logs {
+        events.extend(assembler.push(log));
+    }
+    events.extend(assembler.finish());
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::parse_log_line;
+
+    // Assemble kubelet-timestamped lines from one container
+    fn assemble(lines: &[&str]) -> Vec<K8sLog> {
+        assemble_multiline(lines.iter().map(|line| parse_log_line(line, "ns", "pod", "app")).collect())
+    }
+
+    #[test]
+    fn joins_java_stack_trace() {
+        let events = assemble(&[
+            "2024-01-01T00:00:00Z INFO starting",
+            "2024-01-01T00:00:01Z ERROR Exception in thread \"main\" java.lang.IllegalStateException: boom",
+            "2024-01-01T00:00:01Z \tat com.acme.App.run(App.java:10)",
+            "2024-01-01T00:00:01Z Caused by: java.io.IOException: disk",
+            "2024-01-01T00:00:01Z \t... 3 more",
+            "2024-01-01T00:00:02Z INFO next",
+        ]);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1].message.lines().count(), 4);
+        assert!(events[1].message.ends_with("\t... 3 more"));
+        assert_eq!(events[1].timestamp, "2024-01-01T00:00:01.000000000Z");
+        assert_eq!(events[1].level, "ERROR");
+        assert_eq!(events[2].message, "INFO next");
+    }
+
+    #[test]
+    fn joins_python_traceback() {
+        let events = assemble(&[
+            "2024-01-01T00:00:02Z request failed",
+            "2024-01-01T00:00:02Z Traceback (most recent call last):",
+            "2024-01-01T00:00:02Z   File \"app.py\", line 3, in <module>",
+            "2024-01-01T00:00:02Z     main()",
+            "2024-01-01T00:00:02Z ValueError: bad value",
+            "2024-01-01T00:00:03Z INFO next",
+        ]);
+        assert_eq!(events.len(), 2);
+        assert!(events[0].message.starts_with("request failed\nTraceback"));
+        assert!(events[0].message.ends_with("ValueError: bad value"));
+        assert_eq!(events[0].level, "ERROR");
+    }
+
+    #[test]
+    fn python_traceback_in_a_later_second_starts_an_event() {
+        let events = assemble(&[
+            "2024-01-01T00:00:05Z INFO restarted",
+            "2024-01-01T00:00:06Z Traceback (most recent call last):",
+            "2024-01-01T00:00:06Z   File \"x.py\", line 1",
+            "2024-01-01T00:00:06Z KeyError: 'a'",
+        ]);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].message, "INFO restarted");
+        assert_eq!(events[0].level, "INFO");
+        assert!(events[1].message.ends_with("KeyError: 'a'"));
+        assert_eq!(events[1].level, "ERROR");
+    }
+
+    #[test]
+    fn joins_go_panic() {
+        let events = assemble(&[
+            // Callers drop the blank line Go prints after the panic message
+            "2024-01-01T00:00:04Z panic: runtime error: index out of range",
+            "2024-01-01T00:00:04Z goroutine 1 [running]:",
+            "2024-01-01T00:00:04Z main.main()",
+            "2024-01-01T00:00:04Z \t/app/main.go:12 +0x1d",
+            "2024-01-01T00:00:04Z exit status 2",
+            "2024-01-01T00:00:05Z INFO restarted",
+        ]);
+        assert_eq!(events.len(), 2);
+        assert!(events[0].message.starts_with("panic: runtime error"));
+        assert!(events[0].message.ends_with("exit status 2"));
+        assert_eq!(events[0].level, "FATAL");
+        assert_eq!(events[1].message, "INFO restarted");
+    }
+
+    #[test]
+    fn plain_lines_stay_separate() {
+        let events = assemble(&[
+            "2024-01-01T00:00:00Z INFO one",
+            "2024-01-01T00:00:00Z INFO two",
+        ]);
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn same_second_tolerates_malformed_timestamps() {
+        assert!(same_second("2024-01-01T00:00:00.1Z", "2024-01-01T00:00:00.9Z"));
+        assert!(!same_second("2024-01-01T00:00:00Z", "2024-01-01T00:00:01Z"));
+        assert!(!same_second("short", "short"));
+        // 'é' straddles byte 19
+        assert!(!same_second("2024-01-01T00:00:0é", "2024-01-01T00:00:0é"));
+    }
+}
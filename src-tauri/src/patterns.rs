@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::k8s::{cached_namespace_logs, K8sLog, LogFetchSettings, LogQueryError, LogQueryTiming, LogSourceError, NamespaceLogsQuery};
+use crate::log_parser::LogLevel;
+
+// Log pattern mining
+//
+// A Drain-style template miner: variables are masked first (`<UUID>`, `<IP>`,
+// `<HEX>`, `<NUM>`), then lines with the same token count and leading tokens
+// are compared position by position. A line joins the most similar pattern
+// when enough of its tokens match, and the positions that differ become
+// `<*>`. Only the first line of a multiline event is used.
+
+const DEFAULT_SIMILARITY: f64 = 0.5;
+const DEFAULT_SAMPLES: usize = 3;
+const DEFAULT_PATTERN_LIMIT: usize = 200;
+// Leading tokens that must match exactly before lines are compared
+const PREFIX_TOKENS: usize = 2;
+// Lines longer than this are cut, so one huge line cannot dominate the run
+const MAX_TOKENS: usize = 200;
+
+const WILDCARD: &str = "<*>";
+
+fn uuid_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b").unwrap())
+}
+
+fn ip_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // IPv4 with an optional port, or a full IPv6 address
+    RE.get_or_init(|| Regex::new(r"(?i)\b(?:\d{1,3}\.){3}\d{1,3}(?::\d{1,5})?\b|\b(?:[0-9a-f]{1,4}:){7}[0-9a-f]{1,4}\b").unwrap())
+}
+
+fn hex_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // `0x1f`, or a bare run of 8+ hex digits such as a commit or span ID
+    RE.get_or_init(|| Regex::new(r"(?i)\b0x[0-9a-f]+\b|\b[0-9a-f]{8,}\b").unwrap())
+}
+
+fn number_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // `42`, `1.5`, and numbers with a unit such as `200ms` or `12KiB`
+    RE.get_or_init(|| Regex::new(r"\b\d+(?:\.\d+)?(?:[a-zA-Z%]+)?\b").unwrap())
+}
+
+// Replace the variable parts of a line with placeholders
+pub fn mask_variables(line: &str) -> String {
+    let masked = uuid_regex().replace_all(line, "<UUID>");
+    let masked = ip_regex().replace_all(&masked, "<IP>");
+    let masked = hex_regex().replace_all(&masked, |caps: &regex::Captures| {
+        let text = &caps[0];
+        // Words made only of the letters a-f are not IDs, and plain digits are numbers
+        let digits = text.bytes().any(|b| b.is_ascii_digit());
+        let letters = text.bytes().any(|b| b.is_ascii_alphabetic());
+        if digits && letters {
+            "<HEX>".to_string()
+        } else {
+            text.to_string()
+        }
+    });
+    number_regex().replace_all(&masked, "<NUM>").into_owned()
+}
+
+fn is_variable(token: &str) -> bool {
+    (token.starts_with('<') && token.ends_with('>')) || token.bytes().any(|b| b.is_ascii_digit())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogPattern {
+    // Template with `<*>` where lines differ
+    pub pattern: String,
+    pub count: usize,
+    pub first_seen: String,
+    pub last_seen: String,
+    // Most severe level among the lines
+    pub level: String,
+    pub samples: Vec<K8sLog>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogPatternsResponse {
+    pub patterns: Vec<LogPattern>,
+    // Patterns before `limit` was applied
+    pub patterns_total: usize,
+    // Log events the patterns were mined from
+    pub total: usize,
+    pub errors: Vec<LogSourceError>,
+    pub sources_total: usize,
+    pub sources_failed: usize,
+    pub timing: LogQueryTiming,
+}
+
+struct Cluster {
+    tokens: Vec<String>,
+    count: usize,
+    first_seen: String,
+    last_seen: String,
+    level: Option<LogLevel>,
+    level_name: String,
+    samples: Vec<K8sLog>,
+}
+
+impl Cluster {
+    // Share of positions where the template has the same literal token
+    fn similarity(&self, tokens: &[String]) -> f64 {
+        // Lines are only compared at equal length, so two blank lines match fully
+        if tokens.is_empty() {
+            return 1.0;
+        }
+        let same = self.tokens.iter().zip(tokens)
+            .filter(|(template, token)| template == token)
+            .count();
+        same as f64 / tokens.len() as f64
+    }
+
+    fn add(&mut self, tokens: &[String], log: &K8sLog, max_samples: usize) {
+        for (template, token) in self.tokens.iter_mut().zip(tokens) {
+            if template != token {
+                *template = WILDCARD.to_string();
+            }
+        }
+        self.count += 1;
+        if log.timestamp < self.first_seen {
+            self.first_seen = log.timestamp.clone();
+        }
+        if log.timestamp > self.last_seen {
+            self.last_seen = log.timestamp.clone();
+        }
+        let level = LogLevel::from_name(&log.level);
+        if level > self.level {
+            self.level = level;
+            self.level_name = log.level.clone();
+        }
+        if self.samples.len() < max_samples {
+            self.samples.push(log.clone());
+        }
+    }
+}
+
+// Mine patterns from a set of logs, most frequent first. `similarity` is the
+// share of tokens (0-1) a line must share with a pattern to join it.
+pub fn mine_patterns(logs: &[K8sLog], similarity: f64, max_samples: usize) -> Vec<LogPattern> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    // Token count and leading tokens -> clusters to compare against
+    let mut groups: HashMap<(usize, Vec<String>), Vec<usize>> = HashMap::new();
+
+    for log in logs {
+        let first_line = log.message.lines().next().unwrap_or("");
+        let tokens: Vec<String> = mask_variables(first_line)
+            .split_whitespace()
+            .take(MAX_TOKENS)
+            .map(str::to_string)
+            .collect();
+        let prefix: Vec<String> = tokens.iter()
+            .take(PREFIX_TOKENS)
+            .map(|token| if is_variable(token) { WILDCARD.to_string() } else { token.clone() })
+            .collect();
+
+        let candidates = groups.entry((tokens.len(), prefix)).or_default();
+        let best = candidates.iter()
+            .map(|&index| (index, clusters[index].similarity(&tokens)))
+            .filter(|(_, score)| *score >= similarity)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match best {
+            Some((index, _)) => clusters[index].add(&tokens, log, max_samples),
+            None => {
+                let mut samples = Vec::new();
+                if max_samples > 0 {
+                    samples.push(log.clone());
+                }
+                candidates.push(clusters.len());
+                clusters.push(Cluster {
+                    tokens,
+                    count: 1,
+                    first_seen: log.timestamp.clone(),
+                    last_seen: log.timestamp.clone(),
+                    level: LogLevel::from_name(&log.level),
+                    level_name: log.level.clone(),
+                    samples,
+                });
+            }
+        }
+    }
+
+    let mut patterns: Vec<LogPattern> = clusters.into_iter()
+        .map(|cluster| LogPattern {
+            pattern: cluster.tokens.join(" "),
+            count: cluster.count,
+            first_seen: cluster.first_seen,
+            last_seen: cluster.last_seen,
+            level: cluster.level_name,
+            samples: cluster.samples,
+        })
+        .collect();
+    patterns.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.first_seen.cmp(&b.first_seen)));
+    patterns
+}
+
+// Group the filtered result of a namespace log query into patterns. Shares
+// the result cache with k8s_get_namespace_logs.
+#[tauri::command]
+pub async fn k8s_get_log_patterns(
+    query: NamespaceLogsQuery,
+    similarity: Option<f64>,
+    samples: Option<usize>,
+    limit: Option<usize>,
+    refresh: Option<bool>,
+    concurrency: Option<usize>,
+    pod_timeout_secs: Option<u64>
+) -> Result<LogPatternsResponse, LogQueryError> {
+    let similarity = similarity.unwrap_or(DEFAULT_SIMILARITY);
+    if !(0.0..=1.0).contains(&similarity) {
+        return Err(format!("Similarity must be between 0 and 1, got {}", similarity).into());
+    }
+    let settings = LogFetchSettings::new(concurrency, pod_timeout_secs);
+    let (_, result) = cached_namespace_logs(&query, &settings, refresh.unwrap_or(false)).await?;
+
+    let logs = result.clone();
+    let max_samples = samples.unwrap_or(DEFAULT_SAMPLES);
+    let mut patterns = tokio::task::spawn_blocking(move || mine_patterns(&logs.logs, similarity, max_samples))
+        .await
+        .map_err(|e| format!("Pattern mining failed: {}", e))?;
+    let patterns_total = patterns.len();
+    patterns.truncate(limit.filter(|l| *l > 0).unwrap_or(DEFAULT_PATTERN_LIMIT));
+
+    Ok(LogPatternsResponse {
+        patterns,
+        patterns_total,
+        total: result.logs.len(),
+        errors: result.errors.clone(),
+        sources_total: result.sources_total,
+        sources_failed: result.errors.len(),
+        timing: result.timing.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::parse_log_line;
+
+    fn mine(lines: &[&str]) -> Vec<LogPattern> {
+        let logs: Vec<K8sLog> = lines.iter().map(|line| parse_log_line(line, "ns", "pod", "app")).collect();
+        mine_patterns(&logs, DEFAULT_SIMILARITY, DEFAULT_SAMPLES)
+    }
+
+    #[test]
+    fn masks_variables() {
+        assert_eq!(
+            mask_variables("user 3f2b8c1e-9a4d-4e2f-8b1a-0c9d8e7f6a5b from 10.0.0.12:8080 took 200ms"),
+            "user <UUID> from <IP> took <NUM>"
+        );
+        assert_eq!(mask_variables("span deadbeef01 at 0x1f, retry 3 of 5"), "span <HEX> at <HEX>, retry <NUM> of <NUM>");
+        // Hex-only words and hyphenated names are not variables
+        assert_eq!(mask_variables("cafe added to feed"), "cafe added to feed");
+        assert_eq!(mask_variables("pod web-7 ready"), "pod web-<NUM> ready");
+    }
+
+    #[test]
+    fn merges_differing_tokens_into_wildcards() {
+        let patterns = mine(&[
+            "login succeeded for alice from web",
+            "login succeeded for bob from web",
+            "login succeeded for carol from mobile",
+            "cache miss for key session",
+        ]);
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(patterns[0].pattern, "login succeeded for <*> from <*>");
+        assert_eq!(patterns[0].count, 3);
+        assert_eq!(patterns[0].samples.len(), 3);
+        assert_eq!(patterns[1].pattern, "cache miss for key session");
+    }
+
+    #[test]
+    fn masked_lines_share_a_pattern() {
+        let patterns = mine(&[
+            "request 17 took 120ms",
+            "request 18 took 95ms",
+        ]);
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].pattern, "request <NUM> took <NUM>");
+    }
+
+    #[test]
+    fn blank_lines_form_one_pattern() {
+        let patterns = mine(&["", "   ", ""]);
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].pattern, "");
+        assert_eq!(patterns[0].count, 3);
+    }
+}
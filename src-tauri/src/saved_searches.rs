@@ -0,0 +1,494 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::k8s::LogQueryError;
+use crate::log_parser::{format_timestamp, LogLevel};
+use crate::search::{parse_and_compile, parse_time};
+
+// Saved searches
+//
+//   <app data>/saved-searches.json    every saved search and the next id
+//
+// Exports are a JSON array of searches, which imports read; a library file
+// from before `next_id` was kept is the same array. Queries are parsed when
+// saved or imported, so the library never holds a search that cannot run.
+
+const SAVED_SEARCHES_FILE: &str = "saved-searches.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedSearch {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Search query in the syntax of k8s_validate_search_query
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub deployments: Vec<String>,
+    #[serde(default)]
+    pub severity: Option<String>,
+    // Time window as RFC3339 or relative times such as -1h
+    #[serde(default)]
+    pub since_time: Option<String>,
+    #[serde(default)]
+    pub until: Option<String>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+// A search to create, or to update when `id` is set
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SavedSearchInput {
+    pub id: Option<u64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub query: String,
+    pub namespace: Option<String>,
+    pub deployments: Vec<String>,
+    pub severity: Option<String>,
+    pub since_time: Option<String>,
+    pub until: Option<String>,
+}
+
+impl From<SavedSearch> for SavedSearchInput {
+    fn from(search: SavedSearch) -> Self {
+        SavedSearchInput {
+            id: Some(search.id),
+            name: search.name,
+            description: search.description,
+            query: search.query,
+            namespace: search.namespace,
+            deployments: search.deployments,
+            severity: search.severity,
+            since_time: search.since_time,
+            until: search.until,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SavedSearchRejection {
+    pub name: String,
+    pub error: LogQueryError,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SavedSearchImport {
+    pub added: usize,
+    // Searches that replaced one with the same name
+    pub updated: usize,
+    pub rejected: Vec<SavedSearchRejection>,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+// Check a search before it is stored and return it trimmed
+fn validate(mut input: SavedSearchInput) -> Result<SavedSearchInput, LogQueryError> {
+    input.name = input.name.trim().to_string();
+    if input.name.is_empty() {
+        return Err("Saved search name is required".to_string().into());
+    }
+    input.query = input.query.trim().to_string();
+    if !input.query.is_empty() {
+        parse_and_compile(&input.query).map_err(LogQueryError::InvalidQuery)?;
+    }
+
+    input.description = non_empty(input.description);
+    input.namespace = non_empty(input.namespace);
+    input.deployments = input.deployments.iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    input.severity = non_empty(input.severity);
+    if let Some(ref severity) = input.severity {
+        if LogLevel::from_name(severity).is_none() {
+            return Err(format!("Unknown severity '{}'", severity).into());
+        }
+    }
+
+    input.since_time = non_empty(input.since_time);
+    input.until = non_empty(input.until);
+    let now = Utc::now();
+    let parse_bound = |name: &str, value: &Option<String>| match value {
+        Some(text) => parse_time(text, now)
+            .map(Some)
+            .ok_or_else(|| format!("Invalid {} '{}': expected RFC3339 or a relative time like -15m", name, text)),
+        None => Ok(None),
+    };
+    let since_time = parse_bound("since_time", &input.since_time)?;
+    let until = parse_bound("until", &input.until)?;
+    if let (Some(start), Some(end)) = (since_time, until) {
+        if start > end {
+            return Err("since_time must be before until".to_string().into());
+        }
+    }
+
+    Ok(input)
+}
+
+// Contents of the library file. `next_id` only grows, so the id of a deleted
+// search is never given to another one.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct LibraryState {
+    next_id: u64,
+    searches: Vec<SavedSearch>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LibraryFile {
+    State(LibraryState),
+    Searches(Vec<SavedSearch>),
+}
+
+pub struct SavedSearchLibrary {
+    path: PathBuf,
+    state: LibraryState,
+}
+
+impl SavedSearchLibrary {
+    pub fn open(path: PathBuf) -> Result<Self, String> {
+        let mut state = if path.exists() {
+            let data = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            match serde_json::from_slice(&data) {
+                Ok(LibraryFile::State(state)) => state,
+                Ok(LibraryFile::Searches(searches)) => LibraryState { next_id: 0, searches },
+                Err(e) => return Err(format!("Invalid saved searches in {}: {}", path.display(), e)),
+            }
+        } else {
+            LibraryState::default()
+        };
+        let after_last = state.searches.iter().map(|search| search.id + 1).max().unwrap_or(1);
+        state.next_id = state.next_id.max(after_last);
+        Ok(SavedSearchLibrary { path, state })
+    }
+
+    pub fn list(&self) -> Vec<SavedSearch> {
+        sorted(self.state.searches.clone())
+    }
+
+    // Changes are made to a copy, which replaces the library only once it is
+    // written, so a failed write leaves memory matching the file
+    pub fn save(&mut self, input: SavedSearchInput) -> Result<SavedSearch, LogQueryError> {
+        let input = validate(input)?;
+        let mut state = self.state.clone();
+        let search = upsert(&mut state, input)?;
+        self.replace(state)?;
+        Ok(search)
+    }
+
+    pub fn delete(&mut self, id: u64) -> Result<(), String> {
+        let mut state = self.state.clone();
+        state.searches.retain(|search| search.id != id);
+        if state.searches.len() == self.state.searches.len() {
+            return Err(format!("Unknown saved search {}", id));
+        }
+        self.replace(state)
+    }
+
+    // Add imported searches; one with the name of an existing search replaces it
+    pub fn import(&mut self, imported: Vec<SavedSearch>) -> Result<SavedSearchImport, String> {
+        let mut result = SavedSearchImport { added: 0, updated: 0, rejected: Vec::new() };
+        let mut state = self.state.clone();
+        for search in imported {
+            let name = search.name.clone();
+            let mut input = SavedSearchInput::from(search);
+            // Ids are local to a library
+            input.id = find_by_name(&state.searches, &input.name).map(|existing| existing.id);
+            let updating = input.id.is_some();
+            match validate(input).and_then(|input| upsert(&mut state, input)) {
+                Ok(_) if updating => result.updated += 1,
+                Ok(_) => result.added += 1,
+                Err(error) => result.rejected.push(SavedSearchRejection { name, error }),
+            }
+        }
+        self.replace(state)?;
+        Ok(result)
+    }
+
+    fn replace(&mut self, mut state: LibraryState) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        state.searches = sorted(state.searches);
+        write_json(&self.path, &state)?;
+        self.state = state;
+        Ok(())
+    }
+}
+
+fn sorted(mut searches: Vec<SavedSearch>) -> Vec<SavedSearch> {
+    searches.sort_by_key(|search| search.name.to_lowercase());
+    searches
+}
+
+fn find_by_name<'a>(searches: &'a [SavedSearch], name: &str) -> Option<&'a SavedSearch> {
+    let name = name.trim();
+    searches.iter().find(|search| search.name.eq_ignore_ascii_case(name))
+}
+
+fn upsert(state: &mut LibraryState, input: SavedSearchInput) -> Result<SavedSearch, LogQueryError> {
+    let searches = &mut state.searches;
+    if let Some(existing) = find_by_name(searches, &input.name) {
+        if Some(existing.id) != input.id {
+            return Err(format!("A saved search named '{}' already exists", existing.name).into());
+        }
+    }
+
+    let now = format_timestamp(&Utc::now());
+    let (id, created_at) = match input.id {
+        Some(id) => {
+            let existing = searches.iter().find(|search| search.id == id)
+                .ok_or_else(|| format!("Unknown saved search {}", id))?;
+            (id, existing.created_at.clone())
+        }
+        None => {
+            let id = state.next_id;
+            state.next_id += 1;
+            (id, now.clone())
+        }
+    };
+
+    let search = SavedSearch {
+        id,
+        name: input.name,
+        description: input.description,
+        query: input.query,
+        namespace: input.namespace,
+        deployments: input.deployments,
+        severity: input.severity,
+        since_time: input.since_time,
+        until: input.until,
+        created_at,
+        updated_at: now,
+    };
+    match searches.iter_mut().find(|existing| existing.id == id) {
+        Some(existing) => *existing = search.clone(),
+        None => searches.push(search.clone()),
+    }
+    Ok(search)
+}
+
+fn read_searches(path: &Path) -> Result<Vec<SavedSearch>, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_slice(&data).map_err(|e| format!("Invalid saved searches in {}: {}", path.display(), e))
+}
+
+// Write next to the target and rename, so a crash never leaves a half-written file
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let data = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    fs::write(&temp_path, data).map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+    fs::rename(&temp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("Failed to write {}: {}", path.display(), e)
+    })
+}
+
+static SAVED_SEARCHES: OnceLock<Mutex<SavedSearchLibrary>> = OnceLock::new();
+
+// The library under the app data directory, loaded on first use
+fn saved_searches(app: &AppHandle) -> Result<&'static Mutex<SavedSearchLibrary>, String> {
+    if let Some(library) = SAVED_SEARCHES.get() {
+        return Ok(library);
+    }
+    let data_dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    let library = SavedSearchLibrary::open(data_dir.join(SAVED_SEARCHES_FILE))?;
+    Ok(SAVED_SEARCHES.get_or_init(|| Mutex::new(library)))
+}
+
+#[tauri::command]
+pub async fn saved_searches_list(app: AppHandle) -> Result<Vec<SavedSearch>, String> {
+    let library = saved_searches(&app)?;
+    let library = library.lock().map_err(|_| "Saved searches lock poisoned".to_string())?;
+    Ok(library.list())
+}
+
+// Create a search, or update the one with `id`. The query is rejected with
+// its parse error if it cannot run.
+#[tauri::command]
+pub async fn saved_searches_save(app: AppHandle, search: SavedSearchInput) -> Result<SavedSearch, LogQueryError> {
+    let library = saved_searches(&app)?;
+    let mut library = library.lock().map_err(|_| "Saved searches lock poisoned".to_string())?;
+    library.save(search)
+}
+
+#[tauri::command]
+pub async fn saved_searches_delete(app: AppHandle, id: u64) -> Result<(), String> {
+    let library = saved_searches(&app)?;
+    let mut library = library.lock().map_err(|_| "Saved searches lock poisoned".to_string())?;
+    library.delete(id)
+}
+
+// Write every saved search, or only those in `ids`, to a JSON file
+#[tauri::command]
+pub async fn saved_searches_export(app: AppHandle, path: String, ids: Option<Vec<u64>>) -> Result<usize, String> {
+    let library = saved_searches(&app)?;
+    let mut searches = library.lock().map_err(|_| "Saved searches lock poisoned".to_string())?.list();
+    if let Some(ids) = ids {
+        searches.retain(|search| ids.contains(&search.id));
+    }
+    write_json(Path::new(&path), &searches)?;
+    Ok(searches.len())
+}
+
+// Read searches exported by saved_searches_export. Invalid ones are reported
+// and skipped; the rest are added or replace the search with the same name.
+#[tauri::command]
+pub async fn saved_searches_import(app: AppHandle, path: String) -> Result<SavedSearchImport, String> {
+    let searches = read_searches(Path::new(&path))?;
+    let library = saved_searches(&app)?;
+    let mut library = library.lock().map_err(|_| "Saved searches lock poisoned".to_string())?;
+    library.import(searches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A library file under the system temp dir, removed when dropped
+    struct TestLibrary(PathBuf);
+
+    impl TestLibrary {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("saved-searches-test-{}-{}.json", std::process::id(), name));
+            let _ = fs::remove_file(&path);
+            TestLibrary(path)
+        }
+
+        fn open(&self) -> SavedSearchLibrary {
+            SavedSearchLibrary::open(self.0.clone()).unwrap()
+        }
+    }
+
+    impl Drop for TestLibrary {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn input(name: &str, query: &str) -> SavedSearchInput {
+        SavedSearchInput { name: name.to_string(), query: query.to_string(), ..SavedSearchInput::default() }
+    }
+
+    fn exported(name: &str, query: &str) -> SavedSearch {
+        SavedSearch {
+            id: 99,
+            name: name.to_string(),
+            description: None,
+            query: query.to_string(),
+            namespace: None,
+            deployments: Vec::new(),
+            severity: None,
+            since_time: None,
+            until: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_searches() {
+        assert!(matches!(validate(input("errors", "level:(error")), Err(LogQueryError::InvalidQuery(_))));
+        assert!(matches!(validate(input("  ", "error")), Err(LogQueryError::Request(_))));
+
+        let severity = SavedSearchInput { severity: Some("loud".to_string()), ..input("errors", "error") };
+        assert!(matches!(validate(severity), Err(LogQueryError::Request(message)) if message.contains("loud")));
+
+        let inverted = SavedSearchInput {
+            since_time: Some("2024-01-02T00:00:00Z".to_string()),
+            until: Some("2024-01-01T00:00:00Z".to_string()),
+            ..input("errors", "error")
+        };
+        assert!(matches!(validate(inverted), Err(LogQueryError::Request(message)) if message.contains("before")));
+        let relative = SavedSearchInput { since_time: Some("-5m".to_string()), until: Some("-1h".to_string()), ..input("errors", "") };
+        assert!(validate(relative).is_err());
+
+        let valid = SavedSearchInput {
+            severity: Some(" warn ".to_string()),
+            since_time: Some("-1h".to_string()),
+            deployments: vec![" api ".to_string(), "".to_string()],
+            ..input(" errors ", " level:error ")
+        };
+        let valid = validate(valid).unwrap();
+        assert_eq!((valid.name.as_str(), valid.query.as_str()), ("errors", "level:error"));
+        assert_eq!((valid.severity.as_deref(), valid.deployments), (Some("warn"), vec!["api".to_string()]));
+    }
+
+    #[test]
+    fn names_are_unique_ignoring_case() {
+        let mut state = LibraryState { next_id: 1, searches: Vec::new() };
+        let first = upsert(&mut state, input("Errors", "error")).unwrap();
+        assert!(matches!(upsert(&mut state, input("errors", "panic")), Err(LogQueryError::Request(_))));
+        // Renaming a search to its own name in another case is an update
+        let renamed = SavedSearchInput { id: Some(first.id), ..input("ERRORS", "panic") };
+        assert_eq!(upsert(&mut state, renamed).unwrap().id, first.id);
+        assert_eq!(state.searches.len(), 1);
+    }
+
+    #[test]
+    fn ids_are_not_reused_after_delete() {
+        let file = TestLibrary::new("ids");
+        let mut library = file.open();
+        library.save(input("first", "")).unwrap();
+        let second = library.save(input("second", "")).unwrap();
+        library.delete(second.id).unwrap();
+        assert_eq!(library.save(input("third", "")).unwrap().id, second.id + 1);
+
+        // The counter survives a restart even when the newest search is gone
+        let third = library.list().into_iter().find(|search| search.name == "third").unwrap();
+        library.delete(third.id).unwrap();
+        let mut reopened = file.open();
+        assert_eq!(reopened.save(input("fourth", "")).unwrap().id, third.id + 1);
+    }
+
+    #[test]
+    fn libraries_saved_as_an_array_still_open() {
+        let file = TestLibrary::new("array");
+        write_json(&file.0, &[SavedSearch { id: 7, ..exported("old", "error") }]).unwrap();
+        let mut library = file.open();
+        assert_eq!(library.list()[0].name, "old");
+        assert_eq!(library.save(input("new", "")).unwrap().id, 8);
+    }
+
+    #[test]
+    fn import_replaces_by_name_and_rejects_invalid_searches() {
+        let file = TestLibrary::new("import");
+        let mut library = file.open();
+        let existing = library.save(input("Errors", "error")).unwrap();
+
+        let result = library.import(vec![
+            exported("errors", "level:error"),
+            exported("Timeouts", "timeout"),
+            exported("broken", "(timeout"),
+            exported("", "error"),
+        ]).unwrap();
+        assert_eq!((result.added, result.updated), (1, 1));
+        let rejected: Vec<&str> = result.rejected.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(rejected, ["broken", ""]);
+        assert!(matches!(result.rejected[0].error, LogQueryError::InvalidQuery(_)));
+
+        let searches = library.list();
+        assert_eq!(searches.len(), 2);
+        // The replaced search keeps its local id; imported ids are not used
+        assert_eq!((searches[0].id, searches[0].query.as_str()), (existing.id, "level:error"));
+        assert_ne!(searches[1].id, 99);
+    }
+}
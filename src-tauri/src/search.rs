@@ -0,0 +1,1096 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::k8s::K8sLog;
+
+// Search query language
+//
+//   query    := or_expr
+//   or_expr  := and_expr ("OR" and_expr)*
+//   and_expr := not_expr (["AND"] not_expr)*     terms side by side are ANDed
+//   not_expr := "NOT" not_expr | primary
+//   primary  := "(" or_expr ")" | term
+//   term     := [field ":"] value
+//
+// NOT binds tighter than AND, which binds tighter than OR. Values may be
+// double-quoted to include spaces, keywords or operator characters, and a
+// backslash escapes the next character. `/.../` is a regex.
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SearchField {
+    Message,
+    Pod,
+    Container,
+    Level,
+    Namespace,
+    Timestamp,
+    // Any structured field parsed from the log line, looked up by name
+    Field(String),
+    All,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SearchPattern {
+    Contains(String),
+    Equals(String),
+    StartsWith(String),
+    EndsWith(String),
+    Regex(String),
+    GreaterThan(String),
+    GreaterOrEqual(String),
+    LessThan(String),
+    LessOrEqual(String),
+    Between(String, String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SearchCondition {
+    pub field: SearchField,
+    pub pattern: SearchPattern,
+    // Character offset of the term in the query, for error reporting
+    pub position: usize,
+}
+
+// Parsed search expression tree
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SearchQuery {
+    Condition(SearchCondition),
+    And(Vec<SearchQuery>),
+    Or(Vec<SearchQuery>),
+    Not(Box<SearchQuery>),
+}
+
+// Parse or compile failure with the character offset it was detected at
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SearchError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl SearchError {
+    pub(crate) fn new(message: impl Into<String>, position: usize) -> Self {
+        SearchError {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+// A character of a term value; `literal` is set for quoted or escaped
+// characters, which never act as operators
+#[derive(Debug, Clone, Copy)]
+struct ValueChar {
+    ch: char,
+    literal: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    field: Option<String>,
+    value: Vec<ValueChar>,
+    regex: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(Term),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn is_field_name(chars: &[ValueChar]) -> bool {
+    match chars.first() {
+        Some(first) if first.ch.is_alphabetic() || first.ch == '_' || first.ch == '@' => {}
+        _ => return false,
+    }
+    chars.iter().all(|c| {
+        !c.literal && (c.ch.is_alphanumeric() || matches!(c.ch, '_' | '.' | '@' | '-'))
+    })
+}
+
+struct Tokenizer {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Tokenizer {
+    fn new(query: &str) -> Self {
+        Tokenizer {
+            chars: query.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>, SearchError> {
+        let mut tokens = Vec::new();
+
+        while let Some(c) = self.peek() {
+            let position = self.pos;
+            match c {
+                c if c.is_whitespace() => {
+                    self.pos += 1;
+                }
+                '(' => {
+                    self.pos += 1;
+                    tokens.push(Token { kind: TokenKind::LParen, position });
+                }
+                ')' => {
+                    self.pos += 1;
+                    tokens.push(Token { kind: TokenKind::RParen, position });
+                }
+                _ => {
+                    let term = self.read_term()?;
+                    tokens.push(Token { kind: keyword(&term).unwrap_or(TokenKind::Term(term)), position });
+                }
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    // Read one term up to the next unquoted whitespace or parenthesis
+    fn read_term(&mut self) -> Result<Term, SearchError> {
+        let mut field = None;
+        let mut value: Vec<ValueChar> = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            }
+
+            match c {
+                '"' => self.read_quoted(&mut value)?,
+                '\\' => {
+                    let position = self.pos;
+                    self.pos += 1;
+                    let escaped = self.peek()
+                        .ok_or_else(|| SearchError::new("Dangling escape character", position))?;
+                    self.pos += 1;
+                    value.push(ValueChar { ch: escaped, literal: true });
+                }
+                ':' if field.is_none() && is_field_name(&value) => {
+                    self.pos += 1;
+                    field = Some(value.iter().map(|c| c.ch).collect::<String>());
+                    value.clear();
+                }
+                '/' if value.is_empty() => {
+                    // Only a closed `/.../` standing alone is a regex; `/api/users` stays text
+                    let start = self.pos;
+                    if let Some(regex) = self.read_regex() {
+                        if self.peek().is_none_or(|next| next.is_whitespace() || next == ')') {
+                            return Ok(Term { field, value, regex: Some(regex) });
+                        }
+                    }
+                    self.pos = start + 1;
+                    value.push(ValueChar { ch: '/', literal: false });
+                }
+                _ => {
+                    self.pos += 1;
+                    value.push(ValueChar { ch: c, literal: false });
+                }
+            }
+        }
+
+        Ok(Term { field, value, regex: None })
+    }
+
+    fn read_quoted(&mut self, value: &mut Vec<ValueChar>) -> Result<(), SearchError> {
+        let start = self.pos;
+        self.pos += 1;
+
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '"' => return Ok(()),
+                '\\' => {
+                    let escaped = self.peek()
+                        .ok_or_else(|| SearchError::new("Unterminated quoted string", start))?;
+                    self.pos += 1;
+                    value.push(ValueChar { ch: escaped, literal: true });
+                }
+                _ => value.push(ValueChar { ch: c, literal: true }),
+            }
+        }
+
+        Err(SearchError::new("Unterminated quoted string", start))
+    }
+
+    // Read `/.../`; only `\/` is unescaped, other escapes are left for the regex engine
+    fn read_regex(&mut self) -> Option<String> {
+        self.pos += 1;
+        let mut pattern = String::new();
+
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '/' => return Some(pattern),
+                '\\' if self.peek() == Some('/') => {
+                    self.pos += 1;
+                    pattern.push('/');
+                }
+                '\\' => {
+                    pattern.push('\\');
+                    if let Some(next) = self.peek() {
+                        self.pos += 1;
+                        pattern.push(next);
+                    }
+                }
+                _ => pattern.push(c),
+            }
+        }
+
+        None
+    }
+}
+
+// Unquoted upper-case AND / OR / NOT are operators; anything else is a term
+fn keyword(term: &Term) -> Option<TokenKind> {
+    if term.field.is_some() || term.regex.is_some() || term.value.iter().any(|c| c.literal) {
+        return None;
+    }
+    let text: String = term.value.iter().map(|c| c.ch).collect();
+    match text.as_str() {
+        "AND" => Some(TokenKind::And),
+        "OR" => Some(TokenKind::Or),
+        "NOT" => Some(TokenKind::Not),
+        _ => None,
+    }
+}
+
+fn field_from_name(name: &str) -> SearchField {
+    match name.to_lowercase().as_str() {
+        "message" | "msg" => SearchField::Message,
+        "pod" => SearchField::Pod,
+        "container" | "cont" => SearchField::Container,
+        "level" | "severity" => SearchField::Level,
+        "namespace" | "ns" => SearchField::Namespace,
+        "timestamp" | "time" => SearchField::Timestamp,
+        _ => SearchField::Field(name.to_string()),
+    }
+}
+
+fn collect(chars: &[ValueChar]) -> String {
+    chars.iter().map(|c| c.ch).collect()
+}
+
+// Turn a term value into a pattern. Operator characters only count when
+// they were typed unquoted and unescaped.
+fn parse_pattern(value: &[ValueChar]) -> SearchPattern {
+    let operator = |index: usize, ch: char| value.get(index).map(|c| !c.literal && c.ch == ch).unwrap_or(false);
+
+    if operator(0, '=') {
+        return SearchPattern::Equals(collect(&value[1..]));
+    }
+
+    if operator(0, '^') {
+        return SearchPattern::StartsWith(collect(&value[1..]));
+    }
+
+    if !value.is_empty() && operator(value.len() - 1, '$') {
+        return SearchPattern::EndsWith(collect(&value[..value.len() - 1]));
+    }
+
+    if let Some(index) = (0..value.len().saturating_sub(1)).find(|i| operator(*i, '.') && operator(*i + 1, '.')) {
+        return SearchPattern::Between(collect(&value[..index]), collect(&value[index + 2..]));
+    }
+
+    if operator(0, '>') && operator(1, '=') {
+        return SearchPattern::GreaterOrEqual(collect(&value[2..]));
+    }
+
+    if operator(0, '>') {
+        return SearchPattern::GreaterThan(collect(&value[1..]));
+    }
+
+    if operator(0, '<') && operator(1, '=') {
+        return SearchPattern::LessOrEqual(collect(&value[2..]));
+    }
+
+    if operator(0, '<') {
+        return SearchPattern::LessThan(collect(&value[1..]));
+    }
+
+    SearchPattern::Contains(collect(value))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.pos).map(|t| &t.kind)
+    }
+
+    // Position of the current token, or the end of the input
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map(|t| t.position).unwrap_or(self.end)
+    }
+
+    fn parse_or(&mut self) -> Result<SearchQuery, SearchError> {
+        let mut operands = vec![self.parse_and()?];
+
+        while let Some(TokenKind::Or) = self.peek() {
+            self.pos += 1;
+            operands.push(self.parse_and()?);
+        }
+
+        Ok(if operands.len() == 1 { operands.remove(0) } else { SearchQuery::Or(operands) })
+    }
+
+    fn parse_and(&mut self) -> Result<SearchQuery, SearchError> {
+        let mut operands = vec![self.parse_not()?];
+
+        loop {
+            match self.peek() {
+                Some(TokenKind::And) => {
+                    self.pos += 1;
+                    operands.push(self.parse_not()?);
+                }
+                // Juxtaposed terms are an implicit AND
+                Some(TokenKind::Term(_)) | Some(TokenKind::Not) | Some(TokenKind::LParen) => {
+                    operands.push(self.parse_not()?);
+                }
+                _ => break,
+            }
+        }
+
+        Ok(if operands.len() == 1 { operands.remove(0) } else { SearchQuery::And(operands) })
+    }
+
+    fn parse_not(&mut self) -> Result<SearchQuery, SearchError> {
+        if let Some(TokenKind::Not) = self.peek() {
+            self.pos += 1;
+            let operand = self.parse_not()?;
+            return Ok(SearchQuery::Not(Box::new(operand)));
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<SearchQuery, SearchError> {
+        let position = self.position();
+        let token = self.tokens.get(self.pos).cloned();
+
+        match token.map(|t| t.kind) {
+            Some(TokenKind::LParen) => {
+                self.pos += 1;
+                let inner = self.parse_or()?;
+                match self.peek() {
+                    Some(TokenKind::RParen) => {
+                        self.pos += 1;
+                        Ok(inner)
+                    }
+                    _ => Err(SearchError::new("Missing ')' for '(' opened", position)),
+                }
+            }
+            Some(TokenKind::Term(term)) => {
+                self.pos += 1;
+                condition_from_term(term, position)
+            }
+            Some(TokenKind::RParen) => Err(SearchError::new("Unexpected ')'", position)),
+            Some(TokenKind::And) => Err(SearchError::new("Expected a search term before 'AND'", position)),
+            Some(TokenKind::Or) => Err(SearchError::new("Expected a search term before 'OR'", position)),
+            Some(TokenKind::Not) => unreachable!("NOT is handled by parse_not"),
+            None => Err(SearchError::new("Expected a search term", position)),
+        }
+    }
+}
+
+fn condition_from_term(term: Term, position: usize) -> Result<SearchQuery, SearchError> {
+    let field = term.field.as_deref().map(field_from_name).unwrap_or(SearchField::All);
+
+    let pattern = match term.regex {
+        Some(regex) => SearchPattern::Regex(regex),
+        None if term.value.is_empty() => {
+            let field_name = term.field.unwrap_or_default();
+            return Err(SearchError::new(format!("Missing value for field '{}'", field_name), position));
+        }
+        None => parse_pattern(&term.value),
+    };
+
+    Ok(SearchQuery::Condition(SearchCondition { field, pattern, position }))
+}
+
+// Parse a search query string into an expression tree
+pub fn parse_search_query(query: &str) -> Result<SearchQuery, SearchError> {
+    let tokens = Tokenizer::new(query).tokenize()?;
+    if tokens.is_empty() {
+        return Err(SearchError::new("Empty search query", 0));
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: query.chars().count(),
+    };
+    let expression = parser.parse_or()?;
+
+    // Anything left over can only be an unmatched ')'
+    if parser.pos < parser.tokens.len() {
+        return Err(SearchError::new("Unexpected ')'", parser.position()));
+    }
+
+    Ok(expression)
+}
+
+// Unit family of a parsed quantity. Unitless numbers compare against either
+// family in its base unit: milliseconds for durations, bytes for sizes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum QuantityUnit {
+    Plain,
+    Duration,
+    Bytes,
+}
+
+// Comparison operand, typed from the text the user wrote
+#[derive(Debug, Clone)]
+enum Comparable {
+    Time(DateTime<Utc>),
+    Quantity(f64, QuantityUnit),
+    Text(String),
+}
+
+fn quantity_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^([+-]?(?:\d+\.?\d*|\.\d+)(?:[eE][+-]?\d+)?)\s*([a-zA-Zµ]*)$").unwrap())
+}
+
+fn duration_part_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(\d+(?:\.\d+)?)(ns|us|µs|ms|s|m|h|d)").unwrap())
+}
+
+// Milliseconds per duration unit
+fn duration_unit_ms(unit: &str) -> Option<f64> {
+    match unit {
+        "ns" => Some(1e-6),
+        "us" | "µs" => Some(1e-3),
+        "ms" => Some(1.0),
+        "s" | "sec" => Some(1_000.0),
+        "m" | "min" => Some(60_000.0),
+        "h" => Some(3_600_000.0),
+        "d" => Some(86_400_000.0),
+        _ => None,
+    }
+}
+
+fn byte_unit_size(unit: &str) -> Option<f64> {
+    match unit.to_lowercase().as_str() {
+        "b" => Some(1.0),
+        "k" | "kb" => Some(1e3),
+        "kib" => Some(1024.0),
+        "mb" => Some(1e6),
+        "mib" => Some(1024.0 * 1024.0),
+        "g" | "gb" => Some(1e9),
+        "gib" => Some(1024.0 * 1024.0 * 1024.0),
+        "t" | "tb" => Some(1e12),
+        "tib" => Some(1024.0 * 1024.0 * 1024.0 * 1024.0),
+        _ => None,
+    }
+}
+
+// Parse `42`, `1.5s`, `200ms`, `1m30s`, `10KiB` and similar into a base-unit value
+fn parse_quantity(text: &str) -> Option<(f64, QuantityUnit)> {
+    let text = text.trim();
+
+    if let Some(caps) = quantity_regex().captures(text) {
+        let number: f64 = caps[1].parse().ok()?;
+        let unit = &caps[2];
+        if unit.is_empty() {
+            return Some((number, QuantityUnit::Plain));
+        }
+        if let Some(factor) = duration_unit_ms(unit) {
+            return Some((number * factor, QuantityUnit::Duration));
+        }
+        return byte_unit_size(unit).map(|factor| (number * factor, QuantityUnit::Bytes));
+    }
+
+    // Go-style compound durations such as `1h30m` or `2m3.5s`
+    let mut total = 0.0;
+    let mut consumed = 0;
+    for caps in duration_part_regex().captures_iter(text) {
+        let whole = caps.get(0)?;
+        if whole.start() != consumed {
+            return None;
+        }
+        total += caps[1].parse::<f64>().ok()? * duration_unit_ms(&caps[2])?;
+        consumed = whole.end();
+    }
+    if consumed > 0 && consumed == text.len() {
+        Some((total, QuantityUnit::Duration))
+    } else {
+        None
+    }
+}
+
+// Parse an absolute time (RFC3339 or a bare date) or one relative to now:
+// `now`, `-15m`, `now-2h`
+pub fn parse_time(text: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+
+    let offset = match text.strip_prefix("now") {
+        Some("") => return Some(now),
+        Some(rest) => rest,
+        None => text,
+    };
+    let (sign, amount) = match offset.chars().next() {
+        Some('-') => (-1.0, &offset[1..]),
+        Some('+') => (1.0, &offset[1..]),
+        _ => return None,
+    };
+    match parse_quantity(amount)? {
+        (millis, QuantityUnit::Duration) => {
+            // Out of range offsets such as -99999999d are invalid, not clamped
+            let micros = sign * millis * 1_000.0;
+            if !micros.is_finite() || micros.abs() >= i64::MAX as f64 {
+                return None;
+            }
+            now.checked_add_signed(Duration::microseconds(micros as i64))
+        }
+        _ => None,
+    }
+}
+
+fn parse_comparable(field: &SearchField, text: &str) -> Comparable {
+    if let SearchField::Timestamp = field {
+        if let Some(time) = parse_time(text, Utc::now()) {
+            return Comparable::Time(time);
+        }
+    } else if let Ok(time) = DateTime::parse_from_rfc3339(text.trim()) {
+        return Comparable::Time(time.with_timezone(&Utc));
+    }
+
+    match parse_quantity(text) {
+        Some((value, unit)) => Comparable::Quantity(value, unit),
+        None => Comparable::Text(text.to_string()),
+    }
+}
+
+// Order a field value against an operand. Returns None when the value cannot
+// be read as the operand's type, so `duration:>1000` never matches "slow".
+fn compare_typed(field_value: &str, operand: &Comparable) -> Option<Ordering> {
+    match operand {
+        Comparable::Time(time) => {
+            let value = DateTime::parse_from_rfc3339(field_value.trim()).ok()?;
+            Some(value.with_timezone(&Utc).cmp(time))
+        }
+        Comparable::Quantity(number, unit) => {
+            let (value, value_unit) = parse_quantity(field_value)?;
+            let compatible = value_unit == *unit
+                || value_unit == QuantityUnit::Plain
+                || *unit == QuantityUnit::Plain;
+            if !compatible {
+                return None;
+            }
+            value.partial_cmp(number)
+        }
+        Comparable::Text(text) => Some(field_value.cmp(text.as_str())),
+    }
+}
+
+// Find a structured field by name. Falls back to a loose match that ignores
+// case, `_` and `-`, so `requestId` also finds `request_id`.
+fn lookup_field<'a>(log: &'a K8sLog, name: &str) -> Option<(&'a str, &'a String)> {
+    if let Some((key, value)) = log.fields.get_key_value(name) {
+        return Some((key.as_str(), value));
+    }
+
+    let normalize = |key: &str| -> String {
+        key.chars()
+            .filter(|c| *c != '_' && *c != '-')
+            .flat_map(|c| c.to_lowercase())
+            .collect()
+    };
+    let wanted = normalize(name);
+    log.fields.iter()
+        .find(|(key, _)| normalize(key) == wanted)
+        .map(|(key, value)| (key.as_str(), value))
+}
+
+// Condition matcher with needles folded and regexes and operands built up front
+enum Matcher {
+    Contains(String),
+    Equals(String),
+    StartsWith(String),
+    EndsWith(String),
+    Regex(Regex),
+    Compare(Comparable, fn(Ordering) -> bool),
+    Between(Comparable, Comparable),
+}
+
+struct CompiledCondition {
+    field: SearchField,
+    matcher: Matcher,
+}
+
+enum CompiledNode {
+    Condition(CompiledCondition),
+    And(Vec<CompiledNode>),
+    Or(Vec<CompiledNode>),
+    Not(Box<CompiledNode>),
+}
+
+// Executable form of a search query, built once and run against every line
+pub struct CompiledQuery {
+    root: CompiledNode,
+}
+
+fn compile_condition(condition: &SearchCondition) -> Result<CompiledCondition, SearchError> {
+    let operand = |text: &str| parse_comparable(&condition.field, text);
+    let matcher = match &condition.pattern {
+        SearchPattern::Contains(pattern) => Matcher::Contains(pattern.to_lowercase()),
+        SearchPattern::Equals(pattern) => Matcher::Equals(pattern.to_lowercase()),
+        SearchPattern::StartsWith(pattern) => Matcher::StartsWith(pattern.to_lowercase()),
+        SearchPattern::EndsWith(pattern) => Matcher::EndsWith(pattern.to_lowercase()),
+        SearchPattern::Regex(pattern) => {
+            let regex = Regex::new(pattern)
+                .map_err(|e| SearchError::new(format!("Invalid regex /{}/: {}", pattern, e), condition.position))?;
+            Matcher::Regex(regex)
+        }
+        SearchPattern::GreaterThan(pattern) => Matcher::Compare(operand(pattern), |o| o == Ordering::Greater),
+        SearchPattern::GreaterOrEqual(pattern) => Matcher::Compare(operand(pattern), |o| o != Ordering::Less),
+        SearchPattern::LessThan(pattern) => Matcher::Compare(operand(pattern), |o| o == Ordering::Less),
+        SearchPattern::LessOrEqual(pattern) => Matcher::Compare(operand(pattern), |o| o != Ordering::Greater),
+        SearchPattern::Between(start, end) => Matcher::Between(operand(start), operand(end)),
+    };
+
+    Ok(CompiledCondition {
+        field: condition.field.clone(),
+        matcher,
+    })
+}
+
+fn compile_node(query: &SearchQuery) -> Result<CompiledNode, SearchError> {
+    let compile_all = |operands: &[SearchQuery]| operands.iter().map(compile_node).collect::<Result<Vec<_>, _>>();
+    Ok(match query {
+        SearchQuery::Condition(condition) => CompiledNode::Condition(compile_condition(condition)?),
+        SearchQuery::And(operands) => CompiledNode::And(compile_all(operands)?),
+        SearchQuery::Or(operands) => CompiledNode::Or(compile_all(operands)?),
+        SearchQuery::Not(operand) => CompiledNode::Not(Box::new(compile_node(operand)?)),
+    })
+}
+
+// Compile a parsed query, rejecting invalid regexes before any log is read.
+// Relative times such as `-15m` are resolved once, here.
+pub fn compile_query(query: &SearchQuery) -> Result<CompiledQuery, SearchError> {
+    Ok(CompiledQuery {
+        root: compile_node(query)?,
+    })
+}
+
+// Parse and compile a query string in one step
+pub fn parse_and_compile(query: &str) -> Result<CompiledQuery, SearchError> {
+    compile_query(&parse_search_query(query)?)
+}
+
+impl Matcher {
+    fn is_match(&self, value: &str) -> bool {
+        match self {
+            Matcher::Contains(needle) => value.to_lowercase().contains(needle.as_str()),
+            Matcher::Equals(needle) => value.to_lowercase() == *needle,
+            Matcher::StartsWith(needle) => value.to_lowercase().starts_with(needle.as_str()),
+            Matcher::EndsWith(needle) => value.to_lowercase().ends_with(needle.as_str()),
+            Matcher::Regex(regex) => regex.is_match(value),
+            Matcher::Compare(operand, accept) => compare_typed(value, operand).is_some_and(accept),
+            Matcher::Between(start, end) => {
+                compare_typed(value, start).is_some_and(|o| o != Ordering::Less)
+                    && compare_typed(value, end).is_some_and(|o| o != Ordering::Greater)
+            }
+        }
+    }
+}
+
+// Values an unfielded term is matched against, with the name reported for each
+fn all_field_values(log: &K8sLog) -> impl Iterator<Item = (&str, &String)> {
+    [
+        ("message", &log.message),
+        ("pod", &log.pod),
+        ("container", &log.container),
+        ("level", &log.level),
+        ("namespace", &log.namespace),
+    ]
+    .into_iter()
+    .chain(log.fields.iter().map(|(key, value)| (key.as_str(), value)))
+}
+
+// A field's value with the name it was found under
+fn field_value<'a>(field: &SearchField, log: &'a K8sLog) -> Option<(&'a str, &'a String)> {
+    match field {
+        SearchField::Message => Some(("message", &log.message)),
+        SearchField::Pod => Some(("pod", &log.pod)),
+        SearchField::Container => Some(("container", &log.container)),
+        SearchField::Level => Some(("level", &log.level)),
+        SearchField::Namespace => Some(("namespace", &log.namespace)),
+        SearchField::Timestamp => Some(("timestamp", &log.timestamp)),
+        SearchField::Field(name) => lookup_field(log, name),
+        SearchField::All => None,
+    }
+}
+
+// Value of a field named as in a query, e.g. `level`, `ns` or `requestId`
+pub fn log_field<'a>(log: &'a K8sLog, name: &str) -> Option<&'a str> {
+    field_value(&field_from_name(name), log).map(|(_, value)| value.as_str())
+}
+
+// Read a value as a number; durations come back in milliseconds and sizes in bytes
+pub fn parse_number(text: &str) -> Option<f64> {
+    parse_quantity(text).map(|(value, _)| value)
+}
+
+// Read a duration such as `5m` or `1h30m` in milliseconds; unitless numbers are not durations
+pub fn parse_duration_ms(text: &str) -> Option<f64> {
+    match parse_quantity(text)? {
+        (value, QuantityUnit::Duration) => Some(value),
+        _ => None,
+    }
+}
+
+impl CompiledCondition {
+    // A missing field never matches; wrap the condition in NOT to find absent fields
+    fn field_value<'a>(&self, log: &'a K8sLog) -> Option<(&'a str, &'a String)> {
+        field_value(&self.field, log)
+    }
+
+    fn matches(&self, log: &K8sLog) -> bool {
+        match self.field {
+            SearchField::All => all_field_values(log).any(|(_, value)| self.matcher.is_match(value)),
+            _ => self.field_value(log).is_some_and(|(_, value)| self.matcher.is_match(value)),
+        }
+    }
+
+    fn collect_matched_fields(&self, log: &K8sLog, matched: &mut Vec<String>) {
+        let mut record = |name: &str| {
+            if !matched.iter().any(|m| m == name) {
+                matched.push(name.to_string());
+            }
+        };
+
+        match self.field {
+            SearchField::All => {
+                for (name, value) in all_field_values(log) {
+                    if self.matcher.is_match(value) {
+                        record(name);
+                    }
+                }
+            }
+            _ => {
+                if let Some((name, value)) = self.field_value(log) {
+                    if self.matcher.is_match(value) {
+                        record(name);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl CompiledNode {
+    fn matches(&self, log: &K8sLog) -> bool {
+        match self {
+            CompiledNode::Condition(condition) => condition.matches(log),
+            CompiledNode::And(operands) => operands.iter().all(|operand| operand.matches(log)),
+            CompiledNode::Or(operands) => operands.iter().any(|operand| operand.matches(log)),
+            CompiledNode::Not(operand) => !operand.matches(log),
+        }
+    }
+
+    // Walk the branches that matched; negated branches contribute nothing to highlight
+    fn collect_matched_fields(&self, log: &K8sLog, matched: &mut Vec<String>) {
+        match self {
+            CompiledNode::Condition(condition) => condition.collect_matched_fields(log, matched),
+            CompiledNode::And(operands) | CompiledNode::Or(operands) => {
+                for operand in operands.iter().filter(|operand| operand.matches(log)) {
+                    operand.collect_matched_fields(log, matched);
+                }
+            }
+            CompiledNode::Not(_) => {}
+        }
+    }
+}
+
+// Evaluate a compiled search query against a log entry
+pub fn evaluate_query(log: &K8sLog, query: &CompiledQuery) -> bool {
+    query.root.matches(log)
+}
+
+// Names of the fields that made a log match, for highlighting in the UI.
+// Structured fields are reported by their key, e.g. `requestId`.
+pub fn matched_fields(log: &K8sLog, query: &CompiledQuery) -> Vec<String> {
+    let mut matched = Vec::new();
+    query.root.collect_matched_fields(log, &mut matched);
+    matched
+}
+
+// Lowercase alphanumeric runs of a text. The local log store indexes lines by
+// these, and a search needle's tokens always appear inside the line's tokens.
+pub fn index_tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+}
+
+// Tokens a line must contain to possibly match a query, for narrowing an index
+// lookup. Each token may appear as part of a longer indexed token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TermFilter {
+    Token(String),
+    And(Vec<TermFilter>),
+    Or(Vec<TermFilter>),
+}
+
+impl CompiledCondition {
+    fn term_filter(&self) -> Option<TermFilter> {
+        // Timestamps are not indexed
+        if self.field == SearchField::Timestamp {
+            return None;
+        }
+        let needle = match self.matcher {
+            Matcher::Contains(ref needle)
+            | Matcher::Equals(ref needle)
+            | Matcher::StartsWith(ref needle)
+            | Matcher::EndsWith(ref needle) => needle,
+            // Regexes and comparisons can match text that shares no token with the operand
+            _ => return None,
+        };
+        let tokens: Vec<TermFilter> = index_tokens(needle).map(TermFilter::Token).collect();
+        match tokens.len() {
+            0 => None,
+            1 => tokens.into_iter().next(),
+            _ => Some(TermFilter::And(tokens)),
+        }
+    }
+}
+
+impl CompiledNode {
+    fn term_filter(&self) -> Option<TermFilter> {
+        match self {
+            CompiledNode::Condition(condition) => condition.term_filter(),
+            CompiledNode::And(operands) => {
+                let filters: Vec<TermFilter> = operands.iter().filter_map(CompiledNode::term_filter).collect();
+                match filters.len() {
+                    0 => None,
+                    1 => filters.into_iter().next(),
+                    _ => Some(TermFilter::And(filters)),
+                }
+            }
+            // Every branch must be narrowable, or any line could match
+            CompiledNode::Or(operands) => operands.iter()
+                .map(CompiledNode::term_filter)
+                .collect::<Option<Vec<_>>>()
+                .map(TermFilter::Or),
+            CompiledNode::Not(_) => None,
+        }
+    }
+}
+
+// Index narrowing for a compiled query; None when every line must be checked
+pub fn term_filter(query: &CompiledQuery) -> Option<TermFilter> {
+    query.root.term_filter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::parse_log_line;
+
+    fn term(value: &str) -> SearchQuery {
+        field_term(SearchField::All, SearchPattern::Contains(value.to_string()))
+    }
+
+    fn field_term(field: SearchField, pattern: SearchPattern) -> SearchQuery {
+        SearchQuery::Condition(SearchCondition { field, pattern, position: 0 })
+    }
+
+    // Drop positions so trees can be compared by shape
+    fn shape(query: SearchQuery) -> SearchQuery {
+        match query {
+            SearchQuery::Condition(condition) => SearchQuery::Condition(SearchCondition { position: 0, ..condition }),
+            SearchQuery::And(operands) => SearchQuery::And(operands.into_iter().map(shape).collect()),
+            SearchQuery::Or(operands) => SearchQuery::Or(operands.into_iter().map(shape).collect()),
+            SearchQuery::Not(operand) => SearchQuery::Not(Box::new(shape(*operand))),
+        }
+    }
+
+    fn parse(query: &str) -> SearchQuery {
+        shape(parse_search_query(query).unwrap())
+    }
+
+    fn error(query: &str) -> SearchError {
+        parse_and_compile(query).err().expect("query should be rejected")
+    }
+
+    fn matches(query: &str, line: &str) -> bool {
+        evaluate_query(&parse_log_line(line, "default", "api-0", "app"), &parse_and_compile(query).unwrap())
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(parse("a AND b OR c"), SearchQuery::Or(vec![SearchQuery::And(vec![term("a"), term("b")]), term("c")]));
+        assert_eq!(parse("a OR b AND c"), SearchQuery::Or(vec![term("a"), SearchQuery::And(vec![term("b"), term("c")])]));
+        // Terms side by side are an implicit AND
+        assert_eq!(parse("a b OR c"), parse("a AND b OR c"));
+    }
+
+    #[test]
+    fn parentheses_and_not() {
+        assert_eq!(parse("a AND (b OR c)"), SearchQuery::And(vec![term("a"), SearchQuery::Or(vec![term("b"), term("c")])]));
+        assert_eq!(parse("NOT a b"), SearchQuery::And(vec![SearchQuery::Not(Box::new(term("a"))), term("b")]));
+        assert_eq!(parse("NOT (a OR b)"), SearchQuery::Not(Box::new(SearchQuery::Or(vec![term("a"), term("b")]))));
+        assert_eq!(parse("NOT NOT a"), SearchQuery::Not(Box::new(SearchQuery::Not(Box::new(term("a"))))));
+
+        assert!(matches("level:error AND NOT msg:healthcheck", "ERROR db timeout"));
+        assert!(!matches("level:error AND NOT msg:healthcheck", "ERROR healthcheck failed"));
+        assert!(matches("(timeout OR refused) AND db", "db connection refused"));
+        assert!(!matches("(timeout OR refused) AND db", "cache connection refused"));
+    }
+
+    #[test]
+    fn quoted_phrases() {
+        assert_eq!(parse(r#""connection refused""#), term("connection refused"));
+        assert_eq!(parse(r#"msg:"a OR b""#), field_term(SearchField::Message, SearchPattern::Contains("a OR b".to_string())));
+        // Quoted keywords and operator characters are plain text
+        assert_eq!(parse(r#""AND""#), term("AND"));
+        assert_eq!(parse(r#"">5""#), term(">5"));
+        assert_eq!(parse(r#"\(x"#), term("(x"));
+        assert!(matches(r#""connection refused""#, "upstream connection refused"));
+        assert!(!matches(r#""connection refused""#, "connection was refused"));
+    }
+
+    #[test]
+    fn regex_terms() {
+        assert_eq!(parse(r"/time(out|d out)/"), field_term(SearchField::All, SearchPattern::Regex("time(out|d out)".to_string())));
+        assert_eq!(parse(r"pod:/^api-\d+$/"), field_term(SearchField::Pod, SearchPattern::Regex(r"^api-\d+$".to_string())));
+        assert_eq!(parse(r"/a\/b/"), field_term(SearchField::All, SearchPattern::Regex("a/b".to_string())));
+        // A path is text, not a regex
+        assert_eq!(parse("/api/users"), term("/api/users"));
+        assert!(matches(r"/timed? ?out/", "request timed out"));
+    }
+
+    #[test]
+    fn error_positions() {
+        let e = error("a OR");
+        assert_eq!((e.message.as_str(), e.position), ("Expected a search term", 4));
+        let e = error("(a");
+        assert_eq!(e.position, 0);
+        assert!(e.message.starts_with("Missing ')'"));
+        let e = error("a)");
+        assert_eq!((e.message.as_str(), e.position), ("Unexpected ')'", 1));
+        let e = error("x AND /[/");
+        assert_eq!(e.position, 6);
+        assert!(e.message.starts_with("Invalid regex /[/"));
+        assert_eq!(error("OR a").position, 0);
+        assert_eq!(error(r#"msg:"open"#).position, 4);
+        assert_eq!(error("level:").message, "Missing value for field 'level'");
+        assert_eq!(error("   ").message, "Empty search query");
+    }
+
+    #[test]
+    fn numbers_compare_as_numbers() {
+        // "9" sorts after "1000" as text
+        assert!(!matches("duration:>1000", "level=info duration=9"));
+        assert!(matches("duration:>1000", "level=info duration=10000"));
+        assert!(matches("status:<500", "level=info status=404"));
+        // A value that is not a number never compares
+        assert!(!matches("duration:>1000", "level=info duration=slow"));
+    }
+
+    #[test]
+    fn units_are_normalised() {
+        assert!(matches("duration:>1s", "level=info duration=1.5s"));
+        assert!(matches("duration:>1s", "level=info duration=1500ms"));
+        assert!(!matches("duration:>1s", "level=info duration=200ms"));
+        assert!(matches("duration:>1m", "level=info duration=1m30s"));
+        assert!(matches("duration:<2m", "level=info duration=1m30s"));
+        // Binary and decimal sizes differ
+        assert!(matches("size:>10MB", "level=info size=10MiB"));
+        assert!(!matches("size:>10MiB", "level=info size=10MB"));
+    }
+
+    #[test]
+    fn mismatched_units_do_not_compare() {
+        assert!(!matches("duration:>1s", "level=info duration=10MB"));
+        assert!(!matches("duration:<1s", "level=info duration=10MB"));
+        assert!(!matches("size:>1KB", "level=info size=2s"));
+    }
+
+    #[test]
+    fn timestamps_compare_as_times() {
+        let line = |time: DateTime<Utc>| format!("{} hello", time.to_rfc3339());
+        let now = Utc::now();
+        assert!(matches("timestamp:>-15m", &line(now - Duration::minutes(5))));
+        assert!(!matches("timestamp:>-15m", &line(now - Duration::hours(1))));
+        assert!(matches("timestamp:<2024-01-02", "2024-01-01T23:59:59Z hello"));
+        assert!(matches("timestamp:>2024-01-01T12:00:00+02:00", "2024-01-01T11:00:00Z hello"));
+        assert!(!matches("timestamp:>2024-01-01T12:00:00Z", "2024-01-01T11:00:00Z hello"));
+        // RFC3339 field values compare as times too, across offsets
+        assert!(matches("deadline:>2024-06-01T00:00:00Z", "level=info deadline=2024-05-31T23:00:00-02:00"));
+    }
+
+    #[test]
+    fn ranges() {
+        assert!(matches("duration:1s..2s", "level=info duration=1500ms"));
+        assert!(!matches("duration:1s..2s", "level=info duration=3s"));
+        assert!(matches("status:400..499", "level=info status=404"));
+        assert!(!matches("status:400..499", "level=info status=500"));
+        assert!(matches("timestamp:2024-01-01..2024-01-02", "2024-01-01T12:00:00Z hello"));
+        assert!(!matches("timestamp:2024-01-01..2024-01-02", "2024-01-03T12:00:00Z hello"));
+    }
+
+    #[test]
+    fn relative_times() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_time("now", now), Some(now));
+        assert_eq!(parse_time("-15m", now), Some(now - Duration::minutes(15)));
+        assert_eq!(parse_time("now-1h30m", now), Some(now - Duration::minutes(90)));
+        assert_eq!(parse_time("+2d", now), Some(now + Duration::days(2)));
+        assert_eq!(parse_time("2024-01-01", now), Some(now - Duration::hours(12)));
+        assert_eq!(parse_time("-15", now), None);
+        assert_eq!(parse_time("yesterday", now), None);
+    }
+
+    #[test]
+    fn out_of_range_relative_time_is_rejected() {
+        let now = Utc::now();
+        assert_eq!(parse_time("-99999999d", now), None);
+        assert_eq!(parse_time("+99999999d", now), None);
+        assert_eq!(parse_time(&format!("-{}d", "9".repeat(400)), now), None);
+        // Reachable from a query, where it must not panic
+        assert!(!matches("timestamp:>-99999999d", "2024-01-01T00:00:00Z hello"));
+    }
+}
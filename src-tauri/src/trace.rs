@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use futures::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::k8s::{collect_namespace_logs, k8s_get_namespaces, log_position, K8sLog, LogFetchSettings, LogQueryError, LogSourceError, NamespaceLogsQuery};
+
+// Trace correlation
+//
+// Trace and span IDs are read from structured fields first (`trace_id`,
+// `traceId`, `dd.trace_id`, `trace.id`, `X-B3-TraceId`, Cloud Logging's
+// `logging.googleapis.com/trace`), then from the message: a W3C
+// `traceparent`, a B3 single header (`b3: <trace>-<span>-1`), B3 multi
+// headers, or `trace_id=...` style pairs. IDs compare case-insensitively,
+// and a 64-bit ID (B3 short IDs, Datadog's decimal IDs) matches the low 64
+// bits of a 128-bit one.
+
+// Namespaces searched at once by k8s_find_trace; each fetches its containers concurrently
+const TRACE_NAMESPACE_CONCURRENCY: usize = 4;
+// How far back a search of every namespace looks when no window is given
+const DEFAULT_TRACE_SINCE_SECS: i64 = 3600;
+
+const TRACE_ID_KEYS: &[&str] = &["trace_id", "traceid", "trace.id", "dd.trace_id", "x-b3-traceid", "logging.googleapis.com/trace"];
+const SPAN_ID_KEYS: &[&str] = &["span_id", "spanid", "span.id", "dd.span_id", "x-b3-spanid", "logging.googleapis.com/spanid"];
+const PARENT_SPAN_ID_KEYS: &[&str] = &["parent_span_id", "parentspanid", "parent_id", "parent.id", "x-b3-parentspanid"];
+const SERVICE_KEYS: &[&str] = &["service", "service.name", "service_name", "dd.service", "app", "application"];
+
+fn traceparent_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // version-traceid-parentid-flags
+    RE.get_or_init(|| Regex::new(r"(?i)\b[0-9a-f]{2}-([0-9a-f]{32})-([0-9a-f]{16})-[0-9a-f]{2}\b").unwrap())
+}
+
+fn b3_single_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // b3: {TraceId}-{SpanId}-{SamplingState}-{ParentSpanId}
+    RE.get_or_init(|| Regex::new(r#"(?i)\bb3["']?\s*[=:]\s*["']?([0-9a-f]{32}|[0-9a-f]{16})-([0-9a-f]{16})(?:-[01d](?:-([0-9a-f]{16}))?)?"#).unwrap())
+}
+
+fn message_pair_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // `trace_id=abc`, `"traceId": "abc"`, `X-B3-SpanId: abc`
+    RE.get_or_init(|| Regex::new(r#"(?i)\b(dd\.trace_id|dd\.span_id|x-b3-traceid|x-b3-spanid|x-b3-parentspanid|trace[_.-]?id|span[_.-]?id|parent[_.-]?span[_.-]?id)["']?\s*[=:]\s*["']?([0-9a-z-]{8,64})"#).unwrap())
+}
+
+fn pod_suffix_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // Deployment `name-<hash>-<id>`, DaemonSet/Job `name-<id>`, StatefulSet `name-<n>`
+    RE.get_or_init(|| Regex::new(r"^(.+?)(?:-[a-z0-9]{6,10}-[a-z0-9]{5}|-[a-z0-9]{5}|-\d+)$").unwrap())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TraceContext {
+    pub trace_id: String,
+    pub span_id: Option<String>,
+    pub parent_span_id: Option<String>,
+}
+
+// Lowercase an ID; Datadog writes decimal 64-bit IDs, which become 16 hex digits
+fn normalize_id(key: &str, value: &str) -> String {
+    let value = value.trim().trim_matches('"');
+    // Cloud Logging: projects/<project>/traces/<id>
+    let value = value.rsplit_once("/traces/").map(|(_, id)| id).unwrap_or(value);
+    if key.starts_with("dd.") && value.bytes().all(|b| b.is_ascii_digit()) {
+        if let Ok(id) = value.parse::<u64>() {
+            return format!("{:016x}", id);
+        }
+    }
+    value.to_lowercase()
+}
+
+// Whether two normalised trace IDs name the same trace
+fn same_trace_id(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let hex = |id: &str| id.bytes().all(|b| b.is_ascii_hexdigit());
+    match (a.len(), b.len()) {
+        (16, 32) => hex(a) && b.ends_with(a),
+        (32, 16) => hex(b) && a.ends_with(b),
+        _ => false,
+    }
+}
+
+fn key_is(key: &str, keys: &[&str]) -> bool {
+    keys.iter().any(|candidate| key.eq_ignore_ascii_case(candidate))
+}
+
+// Read the trace context of a log line, if it has one
+pub fn trace_context(log: &K8sLog) -> Option<TraceContext> {
+    let mut context = TraceContext::default();
+
+    // Sorted so the result does not depend on map order
+    let mut keys: Vec<&String> = log.fields.keys().collect();
+    keys.sort();
+    for key in keys {
+        let value = &log.fields[key];
+        if value.trim().is_empty() {
+            continue;
+        }
+        let lower = key.to_lowercase();
+        if key_is(key, TRACE_ID_KEYS) && context.trace_id.is_empty() {
+            context.trace_id = normalize_id(&lower, value);
+        } else if key_is(key, SPAN_ID_KEYS) && context.span_id.is_none() {
+            context.span_id = Some(normalize_id(&lower, value));
+        } else if key_is(key, PARENT_SPAN_ID_KEYS) && context.parent_span_id.is_none() {
+            context.parent_span_id = Some(normalize_id(&lower, value));
+        } else if lower == "traceparent" && context.trace_id.is_empty() {
+            if let Some(caps) = traceparent_regex().captures(value) {
+                context.trace_id = caps[1].to_lowercase();
+                context.span_id = Some(caps[2].to_lowercase());
+            }
+        } else if lower == "b3" && context.trace_id.is_empty() {
+            apply_b3(&mut context, value);
+        }
+    }
+
+    if context.trace_id.is_empty() {
+        let message = log.message.as_str();
+        if let Some(caps) = traceparent_regex().captures(message) {
+            context.trace_id = caps[1].to_lowercase();
+            context.span_id = Some(caps[2].to_lowercase());
+        } else if !apply_b3(&mut context, message) {
+            for caps in message_pair_regex().captures_iter(message) {
+                let key = caps[1].to_lowercase();
+                let value = normalize_id(&key, &caps[2]);
+                let slot = if key.contains("parent") {
+                    &mut context.parent_span_id
+                } else if key.contains("span") {
+                    &mut context.span_id
+                } else {
+                    if context.trace_id.is_empty() {
+                        context.trace_id = value;
+                    }
+                    continue;
+                };
+                slot.get_or_insert(value);
+            }
+        }
+    }
+
+    (!context.trace_id.is_empty()).then_some(context)
+}
+
+fn apply_b3(context: &mut TraceContext, text: &str) -> bool {
+    let Some(caps) = b3_single_regex().captures(text) else {
+        return false;
+    };
+    context.trace_id = caps[1].to_lowercase();
+    context.span_id = Some(caps[2].to_lowercase());
+    context.parent_span_id = caps.get(3).map(|m| m.as_str().to_lowercase());
+    true
+}
+
+// Whether a log line belongs to a trace. Lines without a recognised trace
+// context still match when the ID appears in the message or a field value.
+pub fn log_matches_trace(log: &K8sLog, trace_id: &str) -> bool {
+    let wanted = normalize_id("", trace_id);
+    if let Some(context) = trace_context(log) {
+        // A decimal ID copied from Datadog is compared in its hex form too
+        return same_trace_id(&context.trace_id, &wanted) || same_trace_id(&context.trace_id, &normalize_id("dd.", trace_id));
+    }
+    // Lines without a trace context may still mention the ID in text
+    log.message.to_lowercase().contains(&wanted)
+        || log.fields.values().any(|value| value.to_lowercase().contains(&wanted))
+}
+
+// Service a line came from: a service field when logged, else the pod name
+// without its controller suffixes
+fn service_name(log: &K8sLog) -> String {
+    let mut keys: Vec<&String> = log.fields.keys().filter(|key| key_is(key, SERVICE_KEYS)).collect();
+    keys.sort_by_key(|key| SERVICE_KEYS.iter().position(|candidate| key.eq_ignore_ascii_case(candidate)));
+    if let Some(value) = keys.first().map(|key| log.fields[*key].trim()).filter(|value| !value.is_empty()) {
+        return value.to_string();
+    }
+    pod_suffix_regex().captures(&log.pod)
+        .map(|caps| caps[1].to_string())
+        .unwrap_or_else(|| log.pod.clone())
+}
+
+// A trace to find, and the window each namespace is searched over
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TraceQuery {
+    pub trace_id: String,
+    // Every namespace when empty
+    pub namespaces: Option<Vec<String>>,
+    pub tail: Option<i32>,
+    pub since: Option<i64>,
+    pub since_time: Option<String>,
+    pub until: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TraceEvent {
+    pub log: K8sLog,
+    pub span_id: Option<String>,
+    pub parent_span_id: Option<String>,
+}
+
+// All lines of one service in a trace, in time order
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TraceService {
+    pub service: String,
+    pub namespace: String,
+    pub first_seen: String,
+    pub last_seen: String,
+    // Distinct span IDs in order of first appearance
+    pub spans: Vec<String>,
+    pub events: Vec<TraceEvent>,
+}
+
+// What was searched in one namespace; `error` is set when the namespace
+// could not be searched at all
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TraceNamespaceSummary {
+    pub namespace: String,
+    pub lines: usize,
+    pub sources_total: usize,
+    pub errors: Vec<LogSourceError>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TraceTimeline {
+    pub trace_id: String,
+    // Services ordered by their first line
+    pub services: Vec<TraceService>,
+    pub total: usize,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+    pub namespaces: Vec<TraceNamespaceSummary>,
+    pub total_ms: u64,
+}
+
+// Group trace lines by (namespace, service) into a timeline
+pub fn build_timeline(trace_id: &str, mut logs: Vec<K8sLog>) -> Vec<TraceService> {
+    logs.sort_by(|a, b| log_position(a).cmp(&log_position(b)));
+    let mut services: Vec<TraceService> = Vec::new();
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+    let wanted = normalize_id("", trace_id);
+
+    for log in logs {
+        let context = trace_context(&log).filter(|context| same_trace_id(&context.trace_id, &wanted));
+        let name = service_name(&log);
+        let position = *index.entry((log.namespace.clone(), name.clone())).or_insert_with(|| {
+            services.push(TraceService {
+                service: name,
+                namespace: log.namespace.clone(),
+                first_seen: log.timestamp.clone(),
+                last_seen: log.timestamp.clone(),
+                spans: Vec::new(),
+                events: Vec::new(),
+            });
+            services.len() - 1
+        });
+
+        let service = &mut services[position];
+        service.last_seen = log.timestamp.clone();
+        let (span_id, parent_span_id) = context
+            .map(|context| (context.span_id, context.parent_span_id))
+            .unwrap_or_default();
+        if let Some(ref span) = span_id {
+            if !service.spans.contains(span) {
+                service.spans.push(span.clone());
+            }
+        }
+        service.events.push(TraceEvent { log, span_id, parent_span_id });
+    }
+
+    services
+}
+
+// Find every line of a trace across namespaces, all of them when none are
+// given, in which case the last hour is searched unless a window is set. Each
+// namespace is fetched like k8s_get_namespace_logs with the trace filter, but
+// bypasses its result cache; a namespace that cannot be read is reported in
+// `namespaces` without failing the others.
+#[tauri::command]
+pub async fn k8s_find_trace(
+    query: TraceQuery,
+    concurrency: Option<usize>,
+    pod_timeout_secs: Option<u64>
+) -> Result<TraceTimeline, LogQueryError> {
+    let TraceQuery { trace_id, namespaces, tail, mut since, since_time, until } = query;
+    let trace_id = trace_id.trim().to_string();
+    if trace_id.is_empty() {
+        return Err("Trace ID is required".to_string().into());
+    }
+    let started = Instant::now();
+    let namespaces = match namespaces.filter(|names| !names.is_empty()) {
+        Some(names) => names,
+        None => {
+            // Reading the whole history of every container in the cluster is rarely what was meant
+            if tail.is_none() && since.is_none() && since_time.is_none() {
+                since = Some(DEFAULT_TRACE_SINCE_SECS);
+            }
+            k8s_get_namespaces().await?.into_iter().map(|ns| ns.name).collect()
+        }
+    };
+    let settings = LogFetchSettings::new(concurrency, pod_timeout_secs);
+
+    let results: Vec<_> = futures::stream::iter(namespaces)
+        .map(|namespace| {
+            let query = NamespaceLogsQuery {
+                namespace: namespace.clone(),
+                tail,
+                since,
+                since_time: since_time.clone(),
+                until: until.clone(),
+                trace_id: Some(trace_id.clone()),
+                ..Default::default()
+            };
+            let settings = &settings;
+            async move {
+                // One-off results would only push paged queries out of the cache
+                let result = collect_namespace_logs(&query, settings).await;
+                (namespace, result)
+            }
+        })
+        .buffer_unordered(TRACE_NAMESPACE_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut logs = Vec::new();
+    let mut summaries = Vec::new();
+    for (namespace, result) in results {
+        match result {
+            Ok(collected) => {
+                logs.extend(collected.logs.iter().cloned());
+                summaries.push(TraceNamespaceSummary {
+                    namespace,
+                    lines: collected.logs.len(),
+                    sources_total: collected.sources_total,
+                    errors: collected.errors.clone(),
+                    error: None,
+                });
+            }
+            Err(error) => summaries.push(TraceNamespaceSummary {
+                namespace,
+                lines: 0,
+                sources_total: 0,
+                errors: Vec::new(),
+                error: Some(match error {
+                    LogQueryError::Request(message) => message,
+                    LogQueryError::InvalidQuery(error) => error.message,
+                }),
+            }),
+        }
+    }
+    summaries.sort_by(|a, b| a.namespace.cmp(&b.namespace));
+
+    let total = logs.len();
+    let services = build_timeline(&trace_id, logs);
+    let first_seen = services.iter().map(|service| &service.first_seen).min().cloned();
+    let last_seen = services.iter().map(|service| &service.last_seen).max().cloned();
+
+    Ok(TraceTimeline {
+        trace_id,
+        services,
+        total,
+        first_seen,
+        last_seen,
+        namespaces: summaries,
+        total_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE: &str = "463ac35c9f6413ad48485a3953bb6124";
+    const SHORT_TRACE: &str = "48485a3953bb6124";
+
+    fn log(message: &str, fields: &[(&str, &str)]) -> K8sLog {
+        K8sLog {
+            timestamp: "2024-01-01T10:00:00Z".to_string(),
+            level: "INFO".to_string(),
+            message: message.to_string(),
+            namespace: "default".to_string(),
+            pod: "checkout-7d9f8b6c5d-x2k4q".to_string(),
+            container: "app".to_string(),
+            fields: fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            previous: false,
+            matched_fields: Vec::new(),
+        }
+    }
+
+    fn context(trace_id: &str, span_id: Option<&str>, parent_span_id: Option<&str>) -> Option<TraceContext> {
+        Some(TraceContext {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.map(str::to_string),
+            parent_span_id: parent_span_id.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn w3c_traceparent() {
+        let header = "00-0AF7651916CD43DD8448EB211C80319C-B7AD6B7169203331-01";
+        let expected = context("0af7651916cd43dd8448eb211c80319c", Some("b7ad6b7169203331"), None);
+        assert_eq!(trace_context(&log(&format!("GET /cart traceparent={}", header), &[])), expected);
+        assert_eq!(trace_context(&log("GET /cart", &[("traceparent", header)])), expected);
+    }
+
+    #[test]
+    fn b3_single_header() {
+        let message = format!("b3: {}-a2fb4a1d1a96d312-1-0020000000000001", TRACE);
+        assert_eq!(
+            trace_context(&log(&message, &[])),
+            context(TRACE, Some("a2fb4a1d1a96d312"), Some("0020000000000001"))
+        );
+        // 64-bit trace ID, sampling and parent left out
+        let short = format!("b3={}-a2fb4a1d1a96d312", SHORT_TRACE);
+        assert_eq!(trace_context(&log(&short, &[])), context(SHORT_TRACE, Some("a2fb4a1d1a96d312"), None));
+    }
+
+    #[test]
+    fn b3_multi_headers() {
+        let message = format!(
+            "X-B3-TraceId: {} X-B3-SpanId: a2fb4a1d1a96d312 X-B3-ParentSpanId: 0020000000000001",
+            TRACE
+        );
+        let expected = context(TRACE, Some("a2fb4a1d1a96d312"), Some("0020000000000001"));
+        assert_eq!(trace_context(&log(&message, &[])), expected);
+        let fields = log("request", &[
+            ("X-B3-TraceId", &TRACE.to_uppercase()),
+            ("X-B3-SpanId", "a2fb4a1d1a96d312"),
+            ("X-B3-ParentSpanId", "0020000000000001"),
+        ]);
+        assert_eq!(trace_context(&fields), expected);
+    }
+
+    #[test]
+    fn datadog_decimal_ids_become_hex() {
+        let decimal = u64::from_str_radix(SHORT_TRACE, 16).unwrap().to_string();
+        let line = log("charge", &[("dd.trace_id", &decimal), ("dd.span_id", "255")]);
+        assert_eq!(trace_context(&line), context(SHORT_TRACE, Some("00000000000000ff"), None));
+        // Found from the 128-bit ID, the hex ID or the decimal ID copied from Datadog
+        assert!(log_matches_trace(&line, TRACE));
+        assert!(log_matches_trace(&line, SHORT_TRACE));
+        assert!(log_matches_trace(&line, &decimal));
+    }
+
+    #[test]
+    fn short_ids_match_the_low_bits_of_long_ones() {
+        assert!(same_trace_id(SHORT_TRACE, TRACE));
+        assert!(same_trace_id(TRACE, SHORT_TRACE));
+        assert!(!same_trace_id("463ac35c9f6413ad", TRACE));
+        assert!(!same_trace_id(TRACE, "ffffffffffffffff48485a3953bb6124"));
+        // Only hex IDs are compared by their low bits
+        assert!(!same_trace_id("order-48485a3953", "customer-order-48485a3953"));
+    }
+
+    #[test]
+    fn text_matches_only_without_a_trace_context() {
+        let mention = log(&format!("retrying request for {}", TRACE), &[]);
+        assert!(trace_context(&mention).is_none());
+        assert!(log_matches_trace(&mention, TRACE));
+        assert!(log_matches_trace(&log("upstream call", &[("request", &format!("id {}", TRACE))]), TRACE));
+
+        // A line of another trace that mentions the ID is not part of it
+        let other = log(
+            &format!("trace_id=0af7651916cd43dd8448eb211c80319c caused by {}", TRACE),
+            &[],
+        );
+        assert!(!log_matches_trace(&other, TRACE));
+        assert!(!log_matches_trace(&log("nothing here", &[]), TRACE));
+    }
+}
@@ -1,5 +1,23 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { K8sPod, K8sService, K8sNamespace, K8sDeployment, K8sConfigMap, K8sSecret, K8sLog, PodFilters, ServiceFilters, LogFilters } from '../types/k8s';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { K8sPod, K8sService, K8sNamespace, K8sDeployment, K8sConfigMap, K8sSecret, K8sLog, K8sLogEvent, K8sLogStreamEnd, SearchError, LogQueryError, LogSourceError, PodLogsResponse, NamespaceLogsResponse, ExportFormat, ExportSummary, AggregationRequest, AggregationResponse, LogPatternsResponse, TraceTimeline, StoredLogsQuery, StoredLogsResponse, RetentionPolicy, LogStoreStats, CaptureSpec, CaptureInfo, ImportResponse, SavedSearch, SavedSearchInput, SavedSearchImport, PodFilters, ServiceFilters, LogFilters } from '../types/k8s';
+
+// Filters shared by namespace log queries and exports
+export interface NamespaceLogsOptions {
+  deployments?: string[];
+  pods?: string[];
+  tail?: number;
+  search?: string;
+  severity?: string;
+  traceId?: string;
+  concurrency?: number;
+  podTimeoutSecs?: number;
+  includePrevious?: boolean;
+  since?: number;
+  sinceTime?: string;
+  until?: string;
+  multiline?: boolean;
+}
 
 // Centralized Kubernetes API
 class KubernetesAPI {
@@ -126,42 +144,276 @@ class KubernetesAPI {
   }
 
   // Get logs from a pod
-  async getLogs(namespace: string, pod: string, filters?: LogFilters): Promise<K8sLog[]> {
+  async getLogs(namespace: string, pod: string, filters?: LogFilters): Promise<PodLogsResponse> {
     if (!namespace || !pod) {
       throw new Error('Namespace and pod are required');
     }
     
     try {
-      return await invoke<K8sLog[]>('k8s_get_logs', { namespace, pod, ...filters });
+      return await invoke<PodLogsResponse>('k8s_get_logs', {
+        query: {
+          namespace,
+          pod,
+          container: filters?.container,
+          tail: filters?.tail,
+          since: filters?.since,
+          since_time: filters?.sinceTime,
+          until: filters?.until,
+          previous: filters?.previous,
+          include_previous: filters?.includePrevious,
+          multiline: filters?.multiline
+        }
+      });
     } catch (error) {
       console.error(`Failed to get logs for pod ${pod}:`, error);
       throw error;
     }
   }
 
-  // Get logs from namespace with optional filtering by deployments or pods
+  // Follow logs for one or more pods; returns a function that stops the subscription
+  async followLogs(
+    namespace: string,
+    pods: string[],
+    onLog: (log: K8sLog) => void,
+    options?: {
+      container?: string;
+      tail?: number;
+      onStreamEnd?: (end: K8sLogStreamEnd) => void;
+    }
+  ): Promise<() => Promise<void>> {
+    if (!namespace || pods.length === 0) {
+      throw new Error('Namespace and at least one pod are required');
+    }
+
+    // Listen before subscribing so no early lines are missed
+    let subscriptionId: string | null = null;
+    const unlistenLog: UnlistenFn = await listen<K8sLogEvent>('k8s-log', (event) => {
+      if (event.payload.subscription_id === subscriptionId) {
+        onLog(event.payload.log);
+      }
+    });
+    const unlistenEnd: UnlistenFn = await listen<K8sLogStreamEnd>('k8s-log-stream-end', (event) => {
+      if (event.payload.subscription_id === subscriptionId) {
+        options?.onStreamEnd?.(event.payload);
+      }
+    });
+
+    try {
+      subscriptionId = await invoke<string>('k8s_follow_logs', {
+        namespace,
+        pods,
+        container: options?.container,
+        tail: options?.tail
+      });
+    } catch (error) {
+      unlistenLog();
+      unlistenEnd();
+      console.error(`Failed to follow logs in namespace ${namespace}:`, error);
+      throw error;
+    }
+
+    return async () => {
+      unlistenLog();
+      unlistenEnd();
+      await invoke('k8s_unfollow_logs', { subscriptionId });
+    };
+  }
+
+  // Get logs from namespace with optional filtering by deployments or pods.
+  // Pass a cursor from a previous response to page through the same result.
   async getNamespaceLogs(
     namespace: string,
+    options?: NamespaceLogsOptions & {
+      cursor?: string;
+      pageSize?: number;
+      refresh?: boolean;
+    }
+  ): Promise<NamespaceLogsResponse> {
+    return await invoke<NamespaceLogsResponse>('k8s_get_namespace_logs', {
+      query: this.namespaceLogsQuery(namespace, options),
+      cursor: options?.cursor,
+      pageSize: options?.pageSize,
+      refresh: options?.refresh,
+      concurrency: options?.concurrency,
+      podTimeoutSecs: options?.podTimeoutSecs
+    });
+  }
+
+  // Write the full result of a namespace query to `path`; gzip defaults to a `.gz` extension
+  async exportNamespaceLogs(
+    namespace: string,
+    path: string,
+    format: ExportFormat,
+    options?: NamespaceLogsOptions & { gzip?: boolean }
+  ): Promise<ExportSummary> {
+    return await invoke<ExportSummary>('k8s_export_logs', {
+      query: this.namespaceLogsQuery(namespace, options),
+      path,
+      format,
+      gzip: options?.gzip,
+      concurrency: options?.concurrency,
+      podTimeoutSecs: options?.podTimeoutSecs
+    });
+  }
+
+  async aggregateNamespaceLogs(
+    namespace: string,
+    aggregation: AggregationRequest,
+    options?: NamespaceLogsOptions & { refresh?: boolean }
+  ): Promise<AggregationResponse> {
+    return await invoke<AggregationResponse>('k8s_aggregate_logs', {
+      query: this.namespaceLogsQuery(namespace, options),
+      aggregation,
+      refresh: options?.refresh,
+      concurrency: options?.concurrency,
+      podTimeoutSecs: options?.podTimeoutSecs
+    });
+  }
+
+  async getLogPatterns(
+    namespace: string,
+    options?: NamespaceLogsOptions & { similarity?: number; samples?: number; limit?: number; refresh?: boolean }
+  ): Promise<LogPatternsResponse> {
+    return await invoke<LogPatternsResponse>('k8s_get_log_patterns', {
+      query: this.namespaceLogsQuery(namespace, options),
+      similarity: options?.similarity,
+      samples: options?.samples,
+      limit: options?.limit,
+      refresh: options?.refresh,
+      concurrency: options?.concurrency,
+      podTimeoutSecs: options?.podTimeoutSecs
+    });
+  }
+
+  // Every line of a trace across the given namespaces, or the last hour of all namespaces when empty
+  async findTrace(
+    traceId: string,
+    namespaces?: string[],
     options?: {
-      deployments?: string[];
-      pods?: string[];
       tail?: number;
-      page?: number;
-      search?: string;
-      severity?: string;
-      traceId?: string;
+      since?: number;
+      sinceTime?: string;
+      until?: string;
+      concurrency?: number;
+      podTimeoutSecs?: number;
     }
-  ): Promise<K8sLog[]> {
-    return await invoke('k8s_get_namespace_logs', {
+  ): Promise<TraceTimeline> {
+    return await invoke<TraceTimeline>('k8s_find_trace', {
+      query: {
+        trace_id: traceId,
+        namespaces,
+        tail: options?.tail,
+        since: options?.since,
+        since_time: options?.sinceTime,
+        until: options?.until
+      },
+      concurrency: options?.concurrency,
+      podTimeoutSecs: options?.podTimeoutSecs
+    });
+  }
+
+  // Backend NamespaceLogsQuery; also the key of its result cache
+  private namespaceLogsQuery(namespace: string, options?: NamespaceLogsOptions) {
+    return {
       namespace,
       deployments: options?.deployments,
       pods: options?.pods,
       tail: options?.tail,
-      page: options?.page,
       search: options?.search,
       severity: options?.severity,
-      trace_id: options?.traceId
-    });
+      trace_id: options?.traceId,
+      include_previous: options?.includePrevious,
+      since: options?.since,
+      since_time: options?.sinceTime,
+      until: options?.until,
+      multiline: options?.multiline
+    };
+  }
+
+  // Validate a search query; resolves to null when valid
+  async validateSearchQuery(query: string): Promise<SearchError | null> {
+    try {
+      await invoke('k8s_validate_search_query', { query });
+      return null;
+    } catch (error) {
+      return error as SearchError;
+    }
+  }
+
+  // Summarise partial failures, e.g. "3 of 40 containers failed: Forbidden"
+  describeLogErrors(response: NamespaceLogsResponse): string | null {
+    if (response.sources_failed === 0) return null;
+    const reasons = [...new Set(response.errors.map(e => e.reason))].join(', ');
+    return `${response.sources_failed} of ${response.sources_total} containers failed: ${reasons}`;
+  }
+
+  // Keep streaming a workload's logs into the local store in the background
+  async startCapture(spec: CaptureSpec): Promise<CaptureInfo> {
+    return await invoke<CaptureInfo>('k8s_start_capture', { spec });
+  }
+
+  async stopCapture(captureId: string): Promise<void> {
+    await invoke('k8s_stop_capture', { captureId });
+  }
+
+  async listCaptures(): Promise<CaptureInfo[]> {
+    return await invoke<CaptureInfo[]>('k8s_list_captures');
+  }
+
+  // Read log files, directories or .tar.gz bundles and search them offline
+  async importLogFiles(
+    paths: string[],
+    options?: {
+      namespace?: string; // used when a path does not name one
+      search?: string;
+      store?: boolean; // also keep the lines in the local store
+      limit?: number;
+    }
+  ): Promise<ImportResponse> {
+    return await invoke<ImportResponse>('import_log_files', { paths, ...options });
+  }
+
+  // Search logs kept in the local store, without touching the cluster
+  async searchStoredLogs(query: StoredLogsQuery): Promise<StoredLogsResponse> {
+    return await invoke<StoredLogsResponse>('log_store_search', { query });
+  }
+
+  // Keep already fetched logs in the local store
+  async storeLogs(logs: K8sLog[]): Promise<number> {
+    return await invoke<number>('log_store_append', { logs });
+  }
+
+  async getLogStoreStats(): Promise<LogStoreStats> {
+    return await invoke<LogStoreStats>('log_store_stats');
+  }
+
+  async setLogRetention(retention: RetentionPolicy): Promise<LogStoreStats> {
+    return await invoke<LogStoreStats>('log_store_set_retention', { retention });
+  }
+
+  async clearLogStore(): Promise<void> {
+    await invoke('log_store_clear');
+  }
+
+  async listSavedSearches(): Promise<SavedSearch[]> {
+    return await invoke<SavedSearch[]>('saved_searches_list');
+  }
+
+  // Rejects with a LogQueryError when the query does not parse
+  async saveSearch(search: SavedSearchInput): Promise<SavedSearch> {
+    return await invoke<SavedSearch>('saved_searches_save', { search });
+  }
+
+  async deleteSavedSearch(id: number): Promise<void> {
+    await invoke('saved_searches_delete', { id });
+  }
+
+  async exportSavedSearches(path: string, ids?: number[]): Promise<number> {
+    return await invoke<number>('saved_searches_export', { path, ids });
+  }
+
+  async importSavedSearches(path: string): Promise<SavedSearchImport> {
+    return await invoke<SavedSearchImport>('saved_searches_import', { path });
   }
 
   // Get containers for a pod
@@ -225,4 +477,4 @@ class KubernetesAPI {
 export const k8sAPI = new KubernetesAPI();
 
 // Re-export types for convenience
-export type { K8sPod, K8sService, K8sNamespace, K8sDeployment, K8sConfigMap, K8sSecret, K8sLog, PodFilters, ServiceFilters, LogFilters };
+export type { K8sPod, K8sService, K8sNamespace, K8sDeployment, K8sConfigMap, K8sSecret, K8sLog, K8sLogEvent, K8sLogStreamEnd, SearchError, LogQueryError, LogSourceError, PodLogsResponse, NamespaceLogsResponse, ExportFormat, ExportSummary, AggregationRequest, AggregationResponse, LogPatternsResponse, TraceTimeline, StoredLogsQuery, StoredLogsResponse, RetentionPolicy, LogStoreStats, CaptureSpec, CaptureInfo, ImportResponse, SavedSearch, SavedSearchInput, SavedSearchImport, PodFilters, ServiceFilters, LogFilters };
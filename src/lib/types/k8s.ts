@@ -57,8 +57,304 @@ export interface K8sLog {
   timestamp: string;
   level: string;
   message: string;
+  namespace: string;
   pod: string;
   container: string;
+  // Fields parsed from JSON or logfmt lines, nested keys joined with dots
+  fields: Record<string, string>;
+  // Line came from the previous (last terminated) container instance
+  previous: boolean;
+  // Fields that satisfied the search query, for highlighting
+  matched_fields?: string[];
+}
+
+// Payload of the `k8s-log` event emitted by follow subscriptions
+export interface K8sLogEvent {
+  subscription_id: string;
+  log: K8sLog;
+}
+
+// Payload of the `k8s-log-stream-end` event
+export interface K8sLogStreamEnd {
+  subscription_id: string;
+  pod: string;
+  container: string;
+  error: string | null;
+  subscription_ended: boolean; // no streams of the subscription are left
+}
+
+// A pod/container whose logs could not be fetched; `reason` and `status`
+// come from the API server when it answered (e.g. "Forbidden", 403)
+export interface LogSourceError {
+  pod: string;
+  container: string | null;
+  previous: boolean;
+  reason: string;
+  status: number | null;
+  message: string;
+}
+
+export interface LogSourceTiming {
+  pod: string;
+  container: string | null;
+  previous: boolean;
+  duration_ms: number;
+  lines: number;
+  failed: boolean;
+}
+
+export interface LogQueryTiming {
+  list_ms: number;
+  fetch_ms: number;
+  filter_ms: number;
+  total_ms: number;
+  sources: LogSourceTiming[];
+}
+
+// Result of k8s_get_logs; a previous instance that could not be read is in `errors`
+export interface PodLogsResponse {
+  logs: K8sLog[];
+  errors: LogSourceError[];
+}
+
+// Result of k8s_get_namespace_logs: the merged logs plus per-source failures
+export interface NamespaceLogsResponse {
+  logs: K8sLog[];
+  errors: LogSourceError[];
+  sources_total: number;
+  sources_failed: number;
+  timing: LogQueryTiming;
+  // Matching lines across all pages
+  total: number;
+  // Opaque tokens for the adjacent pages; null at either end
+  next_cursor: string | null;
+  prev_cursor: string | null;
+}
+
+export type ExportFormat = 'ndjson' | 'csv' | 'text' | 'log';
+
+// Result of k8s_export_logs
+export interface ExportSummary {
+  path: string;
+  format: ExportFormat;
+  gzip: boolean;
+  lines: number;
+  bytes: number;
+  errors: LogSourceError[];
+}
+
+// Aggregations over a namespace log query (src-tauri/src/aggregate.rs)
+export type AggregationMetric =
+  | { op: 'count' }
+  | { op: 'distinct_count'; field: string }
+  | { op: 'min'; field: string }
+  | { op: 'max'; field: string }
+  | { op: 'avg'; field: string }
+  | { op: 'sum'; field: string }
+  | { op: 'percentile'; field: string; percentile: number };
+
+export interface AggregationRequest {
+  metrics?: AggregationMetric[];
+  group_by?: string[];
+  bucket?: string;
+  order_by?: number;
+  ascending?: boolean;
+  limit?: number;
+}
+
+export interface AggregationRow {
+  bucket: string | null;
+  group: (string | null)[];
+  values: (number | null)[];
+}
+
+export interface AggregationResponse {
+  metrics: AggregationMetric[];
+  group_by: string[];
+  bucket_ms: number | null;
+  rows: AggregationRow[];
+  groups_total: number;
+  total: number;
+  errors: LogSourceError[];
+  sources_total: number;
+  sources_failed: number;
+  timing: LogQueryTiming;
+}
+
+// Drain-style log patterns (src-tauri/src/patterns.rs)
+export interface LogPattern {
+  pattern: string;
+  count: number;
+  first_seen: string;
+  last_seen: string;
+  level: string;
+  samples: K8sLog[];
+}
+
+export interface LogPatternsResponse {
+  patterns: LogPattern[];
+  patterns_total: number;
+  total: number;
+  errors: LogSourceError[];
+  sources_total: number;
+  sources_failed: number;
+  timing: LogQueryTiming;
+}
+
+// Trace lookup across namespaces (src-tauri/src/trace.rs)
+export interface TraceEvent {
+  log: K8sLog;
+  span_id: string | null;
+  parent_span_id: string | null;
+}
+
+export interface TraceService {
+  service: string;
+  namespace: string;
+  first_seen: string;
+  last_seen: string;
+  spans: string[];
+  events: TraceEvent[];
+}
+
+export interface TraceNamespaceSummary {
+  namespace: string;
+  lines: number;
+  sources_total: number;
+  errors: LogSourceError[];
+  error: string | null;
+}
+
+export interface TraceTimeline {
+  trace_id: string;
+  services: TraceService[];
+  total: number;
+  first_seen: string | null;
+  last_seen: string | null;
+  namespaces: TraceNamespaceSummary[];
+  total_ms: number;
+}
+
+// Search over the local log store (src-tauri/src/log_store.rs)
+export interface StoredLogsQuery {
+  namespace?: string;
+  pods?: string[];
+  search?: string;
+  since_time?: string; // RFC3339 or relative, e.g. "-2h"
+  until?: string;
+  limit?: number;
+}
+
+export interface StoredLogsResponse {
+  logs: K8sLog[]; // newest `limit` matches, oldest first
+  total: number;
+  partitions_scanned: number;
+}
+
+export interface RetentionPolicy {
+  max_bytes: number;
+  max_age_hours: number;
+}
+
+export interface LogStoreStats {
+  path: string;
+  partitions: number;
+  bytes: number;
+  oldest_partition: string | null;
+  newest_partition: string | null;
+  retention: RetentionPolicy;
+}
+
+// A file read by import_log_files, with the source inferred from its path
+export interface ImportedFile {
+  path: string;
+  namespace: string;
+  pod: string;
+  container: string;
+  previous: boolean;
+  lines: number;
+  error: string | null;
+}
+
+export interface ImportResponse {
+  logs: K8sLog[]; // newest `limit` matches, oldest first
+  total: number; // lines matching the search
+  imported: number; // lines read from all files
+  stored: number; // lines written to the local log store
+  files: ImportedFile[];
+}
+
+// Background capture of a workload's logs into the local store
+export interface CaptureSpec {
+  namespace: string;
+  deployments?: string[];
+  label_selector?: string; // e.g. "app=api,tier!=canary"
+}
+
+export type CaptureSourceState = 'Connecting' | 'Streaming' | 'Disconnected' | 'Failed' | 'Gone';
+
+export interface CaptureSourceHealth {
+  pod: string;
+  container: string;
+  state: CaptureSourceState;
+  lines: number;
+  last_timestamp: string | null;
+  last_error: string | null;
+  reconnects: number;
+}
+
+export interface CaptureInfo {
+  id: string;
+  spec: CaptureSpec;
+  started_at: string;
+  last_sync: string | null;
+  sync_error: string | null;
+  sources: CaptureSourceHealth[];
+}
+
+// Search query parse/compile error, with the character offset of the problem
+export interface SearchError {
+  message: string;
+  position: number;
+}
+
+// Error returned by log query commands
+export type LogQueryError =
+  | { kind: 'InvalidQuery'; detail: SearchError }
+  | { kind: 'Request'; detail: string };
+
+// Saved searches (src-tauri/src/saved_searches.rs)
+export interface SavedSearch {
+  id: number;
+  name: string;
+  description: string | null;
+  query: string;
+  namespace: string | null;
+  deployments: string[];
+  severity: string | null;
+  since_time: string | null;
+  until: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+// A search to create, or to update when `id` is set
+export interface SavedSearchInput {
+  id?: number;
+  name: string;
+  description?: string;
+  query?: string;
+  namespace?: string;
+  deployments?: string[];
+  severity?: string;
+  since_time?: string;
+  until?: string;
+}
+
+export interface SavedSearchImport {
+  added: number;
+  updated: number;
+  rejected: { name: string; error: LogQueryError }[];
 }
 
 // Filter types for API calls
@@ -75,5 +371,10 @@ export interface ServiceFilters {
 export interface LogFilters {
   container?: string;
   tail?: number;
-  since?: string;
+  since?: number; // seconds
+  sinceTime?: string; // RFC3339 or relative, e.g. "-15m"
+  until?: string; // RFC3339 or relative, enforced client-side
+  previous?: boolean; // read the last terminated instance
+  includePrevious?: boolean; // merge in a restarted container's previous instance, on by default
+  multiline?: boolean; // join stack traces into one entry, on by default
 }